@@ -0,0 +1,214 @@
+//! CI output mode: plain output without prompts, workflow annotations for
+//! findings and a Markdown job summary.
+
+use crate::redact::redact;
+use std::borrow::Cow;
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+struct Settings {
+    enabled: bool,
+    /// Running in GitHub Actions, which understands `::error::` commands
+    github: bool,
+    assume_yes: bool,
+}
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+/// Job summary path, known only once the configuration is loaded
+static SUMMARY_FILE: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+/// Settings before [`init`], which must not stick: messages printed while
+/// loading the configuration would otherwise lock CI mode off.
+const UNSET: Settings = Settings {
+    enabled: false,
+    github: false,
+    assume_yes: false,
+};
+
+/// Enables CI mode when `force` is set or `CI` is true, as on GitHub
+/// Actions and GitLab CI. Called before the configuration is loaded so its
+/// warnings are already plain. Only the first call has an effect.
+pub fn init(force: bool, assume_yes: bool) {
+    let enabled = force || env_flag("CI");
+    if enabled {
+        colored::control::set_override(false);
+    }
+
+    let _ = SETTINGS.set(Settings {
+        enabled,
+        github: env_flag("GITHUB_ACTIONS"),
+        assume_yes,
+    });
+}
+
+/// Sets where the job summary goes: `summary_file`, else
+/// `$GITHUB_STEP_SUMMARY`. Only the first call has an effect.
+pub fn init_summary(summary_file: Option<&str>) {
+    let summary_file = summary_file.map(PathBuf::from).or_else(|| {
+        env::var_os("GITHUB_STEP_SUMMARY")
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+    });
+    let _ = SUMMARY_FILE.set(summary_file);
+}
+
+fn env_flag(name: &str) -> bool {
+    matches!(env::var(name).as_deref(), Ok("true") | Ok("1"))
+}
+
+fn settings() -> &'static Settings {
+    SETTINGS.get().unwrap_or(&UNSET)
+}
+
+fn summary_file() -> Option<&'static PathBuf> {
+    SUMMARY_FILE.get().and_then(Option::as_ref)
+}
+
+/// Whether CI mode is on.
+pub fn enabled() -> bool {
+    settings().enabled
+}
+
+/// Whether prompts were answered up front with `--yes`.
+pub fn assume_yes() -> bool {
+    settings().assume_yes
+}
+
+/// `println!` for messages that may start with an emoji, which CI mode drops.
+macro_rules! say {
+    () => {
+        println!()
+    };
+    ($($arg:tt)*) => {
+        println!("{}", $crate::ci::plain(&format!($($arg)*)))
+    };
+}
+pub(crate) use say;
+
+/// `eprintln!` counterpart of [`say`].
+macro_rules! esay {
+    ($($arg:tt)*) => {
+        eprintln!("{}", $crate::ci::plain(&format!($($arg)*)))
+    };
+}
+pub(crate) use esay;
+
+/// `message` without emoji and the spaces after them in CI mode, since CI
+/// logs render them poorly; unchanged otherwise.
+pub fn plain(message: &str) -> Cow<'_, str> {
+    if !enabled() || !message.chars().any(is_emoji) {
+        return Cow::Borrowed(message);
+    }
+
+    let mut text = String::with_capacity(message.len());
+    let mut chars = message.chars().peekable();
+    while let Some(c) = chars.next() {
+        if !is_emoji(c) {
+            text.push(c);
+            continue;
+        }
+        while chars.next_if(|&c| is_emoji(c)).is_some() {}
+        while chars.next_if_eq(&' ').is_some() {}
+    }
+    Cow::Owned(text)
+}
+
+/// Emoji used in messages, with their variation selector and joiner. Plain
+/// symbols such as `✓` and `→` are kept.
+fn is_emoji(c: char) -> bool {
+    matches!(
+        c,
+        '\u{23E9}'..='\u{23FA}'
+            | '\u{2600}'..='\u{26FF}'
+            | '\u{2705}'
+            | '\u{274C}'
+            | '\u{2795}'..='\u{2797}'
+            | '\u{1F000}'..='\u{1FAFF}'
+            | '\u{FE0F}'
+            | '\u{200D}'
+    )
+}
+
+#[derive(Clone, Copy)]
+pub enum Level {
+    Error,
+    Warning,
+}
+
+/// Reports a finding in `file` so the CI shows it on the pull request.
+/// Only GitHub Actions has an annotation syntax; elsewhere this is a no-op
+/// since the finding is already in the regular output.
+pub fn annotate(level: Level, file: &str, line: Option<usize>, message: &str) {
+    let settings = settings();
+    if !settings.enabled || !settings.github {
+        return;
+    }
+
+    let command = match level {
+        Level::Error => "error",
+        Level::Warning => "warning",
+    };
+    let mut properties = format!("file={}", escape_property(file));
+    if let Some(line) = line {
+        properties.push_str(&format!(",line={}", line));
+    }
+    println!(
+        "::{} {}::{}",
+        command,
+        properties,
+        escape_data(&redact(message))
+    );
+}
+
+/// Escaping for workflow command messages.
+fn escape_data(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escaping for workflow command properties, which also reserve `:` and `,`.
+fn escape_property(text: &str) -> String {
+    escape_data(text).replace(':', "%3A").replace(',', "%2C")
+}
+
+/// Whether [`summary`] writes anywhere, so callers can skip collecting
+/// output otherwise.
+pub fn summary_enabled() -> bool {
+    enabled() && summary_file().is_some()
+}
+
+/// Fenced Markdown code block for `content`, with a fence longer than any
+/// run of backticks inside it so the output can't close the block early.
+pub fn code_block(lang: &str, content: &str) -> String {
+    let longest = content.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat((longest + 1).max(3));
+    format!(
+        "{}{}\n{}\n{}",
+        fence,
+        lang,
+        content.trim_end_matches('\n'),
+        fence
+    )
+}
+
+/// Appends Markdown to the job summary. Failing to write it only warns,
+/// since the operation itself has already run.
+pub fn summary(markdown: &str) {
+    let Some(path) = summary_file().filter(|_| enabled()) else {
+        return;
+    };
+
+    let result = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| writeln!(file, "{}", redact(markdown)));
+    if let Err(e) = result {
+        esay!("⚠️  Failed to write job summary {}: {}", path.display(), e);
+    }
+}
@@ -1,4 +1,4 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 
 #[derive(Parser)]
 #[command(name = "helmctl")]
@@ -8,9 +8,9 @@ pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
 
-    /// Configuration file path
-    #[arg(short, long, default_value = "helmctl.yaml")]
-    pub config: String,
+    /// Project configuration file (default: nearest helmctl.yaml)
+    #[arg(short, long)]
+    pub config: Option<String>,
 
     /// Enable verbose logging
     #[arg(short, long)]
@@ -19,6 +19,19 @@ pub struct Cli {
     /// Log file path
     #[arg(long)]
     pub log_file: Option<String>,
+
+    /// Continue with defaults when the configuration file is invalid
+    #[arg(long, global = true)]
+    pub ignore_config_errors: bool,
+
+    /// CI output mode: no colors or prompts, with annotations and a job
+    /// summary (default when CI=true)
+    #[arg(long, global = true)]
+    pub ci: bool,
+
+    /// Answer confirmation prompts with yes
+    #[arg(short, long, global = true)]
+    pub yes: bool,
 }
 
 #[derive(Subcommand)]
@@ -34,8 +47,14 @@ pub enum Commands {
         environment: Option<String>,
 
         /// Strict mode - fail on warnings
-        #[arg(short, long)]
-        strict: bool,
+        #[arg(
+            short,
+            long,
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "true"
+        )]
+        strict: Option<bool>,
 
         /// Validate templates only
         #[arg(long)]
@@ -59,17 +78,29 @@ pub enum Commands {
         #[arg(long)]
         diff: bool,
 
+        /// With --diff, exit with code 2 when changes are pending
+        #[arg(long, requires = "diff")]
+        detailed_exitcode: bool,
+
         /// Skip dependency update
-        #[arg(long)]
-        skip_deps: bool,
+        #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+        skip_deps: Option<bool>,
 
-        /// Concurrency level
-        #[arg(short, long, default_value = "1")]
-        concurrency: u8,
+        /// Concurrency level (default: 1)
+        #[arg(short, long)]
+        concurrency: Option<u8>,
 
         /// Kubernetes context
         #[arg(long)]
         context: Option<String>,
+
+        /// Install required helm plugins that are missing or too old
+        #[arg(long)]
+        install_missing: bool,
+
+        /// Configured profile supplying environment, context and namespace
+        #[arg(long)]
+        profile: Option<String>,
     },
     /// Deploy directly to Kubernetes cluster
     K8sDeploy {
@@ -85,17 +116,61 @@ pub enum Commands {
         #[arg(short, long)]
         context: Option<String>,
 
-        /// Dry run mode
+        /// Dry run mode (client-side unless --dry-run=server is given)
+        #[arg(
+            long,
+            value_enum,
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "client"
+        )]
+        dry_run: Option<DryRunMode>,
+
+        /// Apply on the server (server-side apply)
         #[arg(long)]
-        dry_run: bool,
+        server_side: bool,
 
-        /// Wait for deployment to complete
-        #[arg(short, long)]
-        wait: bool,
+        /// Field manager name for server-side apply
+        #[arg(long, requires = "server_side")]
+        field_manager: Option<String>,
+
+        /// Take ownership of fields managed by other field managers
+        #[arg(long, requires = "server_side")]
+        force_conflicts: bool,
+
+        /// Delete resources matching --selector that are not in the manifests
+        #[arg(long, requires = "selector")]
+        prune: bool,
+
+        /// Label selector used to scope the apply and pruning
+        #[arg(short = 'l', long)]
+        selector: Option<String>,
+
+        /// Show a server-side diff against the cluster instead of applying
+        #[arg(long, conflicts_with_all = ["dry_run", "wait"])]
+        diff: bool,
+
+        /// With --diff, exit with code 2 when changes are pending
+        #[arg(long, requires = "diff")]
+        detailed_exitcode: bool,
+
+        /// Wait for Deployments, StatefulSets, DaemonSets and Jobs to finish rolling out
+        #[arg(
+            short,
+            long,
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "true"
+        )]
+        wait: Option<bool>,
+
+        /// Timeout for wait (in seconds, default: 300)
+        #[arg(long)]
+        timeout: Option<u32>,
 
-        /// Timeout for wait (in seconds)
-        #[arg(long, default_value = "300")]
-        timeout: u32,
+        /// Configured profile supplying environment, context and namespace
+        #[arg(long)]
+        profile: Option<String>,
     },
     /// Rollback a Helmfile release
     Rollback {
@@ -137,9 +212,30 @@ pub enum Commands {
         #[arg(long)]
         context: Option<String>,
 
+        /// Namespace for releases that do not set one (defaults to the
+        /// context's namespace)
+        #[arg(short, long)]
+        namespace: Option<String>,
+
         /// Show detailed status
+        #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+        detailed: Option<bool>,
+
+        /// Keep refreshing until all selected releases are healthy
+        #[arg(short, long)]
+        watch: bool,
+
+        /// Refresh interval for watch mode (in seconds)
+        #[arg(long, default_value = "5", requires = "watch")]
+        interval: u64,
+
+        /// Timeout for watch mode (in seconds)
+        #[arg(long, requires = "watch")]
+        timeout: Option<u32>,
+
+        /// Configured profile supplying environment, context and namespace
         #[arg(long)]
-        detailed: bool,
+        profile: Option<String>,
     },
     /// Validate Helmfile templates
     Validate {
@@ -155,11 +251,122 @@ pub enum Commands {
         #[arg(long)]
         syntax_only: bool,
     },
+    /// Render manifests offline into a directory tree, one file per object
+    Render {
+        /// Path to helmfile
+        #[arg(short, long, default_value = "helmfile.yaml")]
+        file: String,
+
+        /// Environment to render
+        #[arg(short, long)]
+        environment: Option<String>,
+
+        /// Directory to write `<release>/<kind>-<name>.yaml` files to
+        #[arg(long)]
+        out_dir: String,
+
+        /// Empty the output directory first, removing deleted releases
+        #[arg(long)]
+        clean: bool,
+    },
+    /// Diff the rendered manifests of two environments, offline
+    Compare {
+        /// Path to helmfile
+        #[arg(short, long, default_value = "helmfile.yaml")]
+        file: String,
+
+        /// The two environments to compare, e.g. -e staging -e prod
+        #[arg(short, long, required = true)]
+        environment: Vec<String>,
+
+        /// Differences to ignore
+        #[arg(long, value_enum, value_delimiter = ',')]
+        ignore: Vec<NoiseFilter>,
+    },
+    /// Diff rendered manifests between two git revisions, offline
+    DiffRev {
+        /// Base revision, e.g. main or origin/main
+        base: String,
+
+        /// Head revision (default: the working tree)
+        head: Option<String>,
+
+        /// Path to helmfile, relative to the current directory
+        #[arg(short, long, default_value = "helmfile.yaml")]
+        file: String,
+
+        /// Environment to render
+        #[arg(short, long)]
+        environment: Option<String>,
+
+        /// Differences to ignore
+        #[arg(long, value_enum, value_delimiter = ',')]
+        ignore: Vec<NoiseFilter>,
+    },
+    /// Compare declared, deployed and latest available chart versions
+    Outdated {
+        /// Path to helmfile
+        #[arg(short, long, default_value = "helmfile.yaml")]
+        file: String,
+
+        /// Release name (optional, check all if not specified)
+        #[arg(short, long)]
+        release: Option<String>,
+
+        /// Helm repository cache directory containing <repo>-index.yaml files
+        #[arg(long)]
+        repo_cache: Option<String>,
+
+        /// Do not query the cluster for deployed versions
+        #[arg(long)]
+        offline: bool,
+    },
+    /// Show the merged values of a release and where each one is set
+    Values {
+        /// Release name
+        release: String,
+
+        /// Path to helmfile
+        #[arg(short, long, default_value = "helmfile.yaml")]
+        file: String,
+
+        /// Environment
+        #[arg(short, long)]
+        environment: Option<String>,
+
+        /// Extra value as passed to `helmfile --set` (repeatable)
+        #[arg(long = "set", value_name = "KEY=VALUE")]
+        set: Vec<String>,
+
+        /// Compare with the chart's defaults or the values currently deployed
+        #[arg(long, value_enum)]
+        compare: Option<ValuesBaseline>,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "text")]
+        output: OutputFormat,
+    },
     /// Manage configuration
     Config {
         #[command(subcommand)]
         action: ConfigAction,
     },
+    /// Inspect and manage sops-encrypted values files
+    Secrets {
+        #[command(subcommand)]
+        action: SecretsAction,
+    },
+    /// Manage the helm plugins helmctl depends on
+    Plugins {
+        #[command(subcommand)]
+        action: PluginsAction,
+    },
+    /// Check tools, plugins, kubeconfig, cluster access and configuration
+    Doctor {
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "text")]
+        output: OutputFormat,
+    },
     /// Switch Kubernetes context
     Context {
         #[command(subcommand)]
@@ -169,22 +376,117 @@ pub enum Commands {
 
 #[derive(Subcommand)]
 pub enum ConfigAction {
-    /// Show current configuration
-    Show,
+    /// Show the effective configuration
+    Show {
+        /// Show which layer each value comes from
+        #[arg(long)]
+        origin: bool,
+    },
     /// Initialize default configuration
     Init,
-    /// Set configuration value
+    /// Set a configuration value in the project file
     Set {
-        /// Configuration key
+        /// Dotted configuration key, e.g. repositories[1].url or profiles.prod.context
         key: String,
-        /// Configuration value
+        /// Configuration value (lists and mappings in YAML flow syntax)
         value: String,
     },
-    /// Get configuration value
+    /// Get an effective configuration value
     Get {
-        /// Configuration key
+        /// Dotted configuration key, e.g. repositories[1].url or profiles.prod.context
         key: String,
     },
+    /// Remove a configuration value from the project file
+    Unset {
+        /// Dotted configuration key, e.g. repositories[1].url or profiles.prod.context
+        key: String,
+    },
+    /// Manage chart repositories
+    Repo {
+        #[command(subcommand)]
+        action: RepoAction,
+    },
+    /// Report unknown keys, type errors and invalid values
+    Validate,
+    /// Print the JSON Schema for the configuration file
+    Schema,
+}
+
+#[derive(Subcommand)]
+pub enum RepoAction {
+    /// Add a repository
+    Add {
+        /// Repository name
+        name: String,
+        /// Repository URL
+        url: String,
+        /// Replace the URL of an existing repository
+        #[arg(long)]
+        force: bool,
+    },
+    /// Remove a repository
+    Remove {
+        /// Repository name
+        name: String,
+    },
+    /// List configured repositories
+    List,
+}
+
+#[derive(Subcommand)]
+pub enum PluginsAction {
+    /// List installed helm plugins and check required ones
+    List,
+    /// Install required helm plugins that are missing or too old
+    Install {
+        /// Plugin to (re)install (default: every missing or outdated required plugin)
+        plugin: Option<String>,
+
+        /// Local .tgz archive, plugin directory or URL to install from
+        #[arg(long, requires = "plugin")]
+        from: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SecretsAction {
+    /// Verify that encrypted values referenced by the helmfile can be decrypted
+    Check {
+        /// Path to helmfile
+        #[arg(short, long, default_value = "helmfile.yaml")]
+        file: String,
+
+        /// Environment whose values files are checked
+        #[arg(short, long)]
+        environment: Option<String>,
+
+        /// Release name (optional, check all if not specified)
+        #[arg(short, long)]
+        release: Option<String>,
+    },
+    /// Print the decrypted contents of an encrypted file
+    View {
+        /// Encrypted file
+        file: String,
+    },
+    /// Edit an encrypted file in $EDITOR
+    Edit {
+        /// Encrypted file
+        file: String,
+    },
+    /// Rotate the data key of an encrypted file
+    Rotate {
+        /// Encrypted file
+        file: String,
+
+        /// Age recipient to add while rotating
+        #[arg(long)]
+        add_age: Option<String>,
+
+        /// Age recipient to remove while rotating
+        #[arg(long)]
+        rm_age: Option<String>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -193,9 +495,95 @@ pub enum ContextAction {
     List,
     /// Switch to a context
     Use {
-        /// Context name
+        /// Context name, alias, unique prefix or fuzzy match; `-` for the
+        /// previous context
         context: String,
     },
     /// Show current context
     Current,
+    /// Show server, user and namespace of a context
+    Info {
+        /// Context name or alias (defaults to the current context)
+        context: Option<String>,
+    },
+    /// Define, remove or list context aliases
+    Alias {
+        /// Alias name (lists aliases when omitted)
+        name: Option<String>,
+        /// Context the alias points to
+        #[arg(requires = "name")]
+        context: Option<String>,
+        /// Remove the alias instead
+        #[arg(long, requires = "name", conflicts_with = "context")]
+        remove: bool,
+    },
+    /// Show recently used contexts
+    History,
+    /// Print `export KUBECONFIG=...` for a private kubeconfig holding only
+    /// this context, for use with `eval "$(helmctl context env <ctx>)"`
+    Env {
+        /// Context name or alias
+        context: String,
+    },
+    /// Start a subshell bound to a context without touching other terminals
+    Shell {
+        /// Context name or alias
+        context: String,
+    },
+    /// Show or set the default namespace of a context
+    Namespace {
+        /// Namespace to make the default (shows the current one when omitted)
+        namespace: Option<String>,
+
+        /// Context name or alias (defaults to the current context)
+        #[arg(long)]
+        context: Option<String>,
+    },
+    /// Probe connectivity, credentials and permissions of contexts
+    Check {
+        /// Context name or alias (defaults to the current context)
+        context: Option<String>,
+
+        /// Check every context in the kubeconfig
+        #[arg(long, conflicts_with = "context")]
+        all: bool,
+
+        /// Seconds to wait for each API server
+        #[arg(long, default_value = "5")]
+        timeout: u64,
+
+        /// Context whose failure only produces a warning (repeatable)
+        #[arg(long = "optional", value_name = "CONTEXT")]
+        optional: Vec<String>,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ValuesBaseline {
+    /// The chart's default values (`helm show values`)
+    Defaults,
+    /// The values of the installed release (`helm get values`)
+    Deployed,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum NoiseFilter {
+    /// `metadata.namespace` of every object
+    Namespaces,
+    /// `spec.replicas` of every object
+    Replicas,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum DryRunMode {
+    /// Only print the objects that would be sent
+    Client,
+    /// Submit the request to the server without persisting it
+    Server,
 }
@@ -0,0 +1,255 @@
+//! Read access to the cluster shared by the commands.
+//! Requests go through kubectl by default. Built with the `native-k8s`
+//! feature, helmctl talks to the API server directly and only falls back to
+//! kubectl when the native client fails and kubectl is installed.
+
+#[cfg(feature = "native-k8s")]
+mod native;
+
+use crate::tools;
+use serde_json::Value;
+use std::process::Command;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+static CONTEXT: OnceLock<String> = OnceLock::new();
+
+/// Resource collections helmctl reads.
+#[derive(Clone, Copy)]
+pub enum Resource {
+    Pods,
+    Secrets,
+    Events,
+}
+
+impl Resource {
+    fn kubectl_name(self) -> &'static str {
+        match self {
+            Resource::Pods => "pods",
+            Resource::Secrets => "secrets",
+            Resource::Events => "events",
+        }
+    }
+}
+
+/// Targets `context` instead of the kubeconfig's current context for the
+/// rest of the process. Only the first call has an effect.
+pub fn select_context(context: &str) {
+    let _ = CONTEXT.set(context.to_string());
+}
+
+fn selected_context() -> Option<&'static str> {
+    CONTEXT.get().map(String::as_str)
+}
+
+fn kubectl() -> Command {
+    let mut cmd = tools::command("kubectl");
+    if let Some(context) = selected_context() {
+        cmd.arg("--context").arg(context);
+    }
+    cmd
+}
+
+/// Runs the native implementation when compiled in, returning `None` when
+/// the caller should fall back to kubectl.
+#[cfg(feature = "native-k8s")]
+fn try_native<T>(f: impl FnOnce() -> Result<T, String>) -> Option<Result<T, String>> {
+    match f() {
+        Ok(value) => Some(Ok(value)),
+        Err(e) if tools::resolve("kubectl").is_none() => Some(Err(e)),
+        Err(_) => None,
+    }
+}
+
+/// Whether manifests can be applied without kubectl.
+pub fn native_available() -> bool {
+    cfg!(feature = "native-k8s")
+}
+
+/// Lists objects as JSON, in the shape `kubectl get -o json` returns them.
+/// Without `namespace` the context's default namespace is used.
+pub fn list(
+    resource: Resource,
+    namespace: Option<&str>,
+    labels: Option<&str>,
+    fields: Option<&str>,
+) -> Result<Vec<Value>, String> {
+    #[cfg(feature = "native-k8s")]
+    if let Some(result) = try_native(|| native::list(resource, namespace, labels, fields)) {
+        return result;
+    }
+
+    let mut cmd = kubectl();
+    cmd.args(["get", resource.kubectl_name(), "--output", "json"]);
+    if let Some(ns) = namespace {
+        cmd.args(["--namespace", ns]);
+    }
+    if let Some(labels) = labels {
+        cmd.args(["--selector", labels]);
+    }
+    if let Some(fields) = fields {
+        cmd.args(["--field-selector", fields]);
+    }
+
+    let output = cmd
+        .output()
+        .map_err(|e| format!("Failed to execute kubectl: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "Failed to list {}: {}",
+            resource.kubectl_name(),
+            stderr.trim()
+        ));
+    }
+
+    let list: Value = serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("Failed to parse kubectl output: {}", e))?;
+    Ok(list["items"].as_array().cloned().unwrap_or_default())
+}
+
+/// `gitVersion` of the API server for the current context.
+pub fn server_version() -> Result<String, String> {
+    #[cfg(feature = "native-k8s")]
+    if let Some(result) = try_native(native::server_version) {
+        return result;
+    }
+
+    let output = kubectl()
+        .args(["get", "--raw", "/version", "--request-timeout=5s"])
+        .output()
+        .map_err(|e| format!("Failed to execute kubectl: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(stderr
+            .lines()
+            .last()
+            .unwrap_or("unreachable")
+            .trim()
+            .to_string());
+    }
+
+    serde_json::from_slice::<Value>(&output.stdout)
+        .ok()
+        .and_then(|v| v["gitVersion"].as_str().map(str::to_string))
+        .ok_or_else(|| "unexpected /version response".to_string())
+}
+
+/// Outcome of probing a context.
+pub struct Probe {
+    pub version: String,
+    pub latency: Duration,
+}
+
+/// Checks that `context` answers within `timeout` and that its credentials
+/// may list pods in `namespace`. Independent of [`select_context`], so
+/// several contexts can be probed from parallel threads. Errors carry the
+/// client's message for classification by the caller.
+pub fn probe(context: &str, namespace: &str, timeout: Duration) -> Result<Probe, String> {
+    #[cfg(feature = "native-k8s")]
+    if let Some(result) = try_native(|| native::probe(context, namespace, timeout)) {
+        return result;
+    }
+
+    let raw = |path: &str| {
+        let output = tools::command("kubectl")
+            .arg("--context")
+            .arg(context)
+            .arg(format!("--request-timeout={}s", timeout.as_secs().max(1)))
+            .args(["get", "--raw", path])
+            .output()
+            .map_err(|e| format!("Failed to execute kubectl: {}", e))?;
+        if output.status.success() {
+            Ok(output.stdout)
+        } else {
+            Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+        }
+    };
+
+    let start = Instant::now();
+    let version = raw("/version")?;
+    let latency = start.elapsed();
+    raw(&format!("/api/v1/namespaces/{}/pods?limit=1", namespace))?;
+
+    let version = serde_json::from_slice::<Value>(&version)
+        .ok()
+        .and_then(|v| v["gitVersion"].as_str().map(str::to_string))
+        .unwrap_or_else(|| "unknown".to_string());
+    Ok(Probe { version, latency })
+}
+
+/// Whether `name` exists in the cluster.
+pub fn namespace_exists(name: &str) -> Result<bool, String> {
+    #[cfg(feature = "native-k8s")]
+    if let Some(result) = try_native(|| native::namespace_exists(name)) {
+        return result;
+    }
+
+    let output = kubectl()
+        .args([
+            "get",
+            "namespace",
+            name,
+            "--ignore-not-found",
+            "--output",
+            "name",
+        ])
+        .output()
+        .map_err(|e| format!("Failed to execute kubectl: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "Failed to look up namespace {}: {}",
+            name,
+            stderr.trim()
+        ));
+    }
+
+    Ok(!String::from_utf8_lossy(&output.stdout).trim().is_empty())
+}
+
+/// Status of the latest revision of a helm release, read from the labels
+/// of the release secrets helm's default storage driver writes. `None`
+/// when no release secret is visible.
+pub fn release_status(name: &str, namespace: Option<&str>) -> Result<Option<String>, String> {
+    let secrets = list(
+        Resource::Secrets,
+        namespace,
+        Some(&format!("owner=helm,name={}", name)),
+        None,
+    )?;
+
+    Ok(secrets
+        .iter()
+        .filter_map(|secret| {
+            let labels = &secret["metadata"]["labels"];
+            let revision: u64 = labels["version"].as_str()?.parse().ok()?;
+            Some((revision, labels["status"].as_str()?.to_string()))
+        })
+        .max_by_key(|(revision, _)| *revision)
+        .map(|(_, status)| status))
+}
+
+/// Server-side applies `objects` with the native client. Returns one
+/// `kind/name` line per applied object.
+#[cfg(feature = "native-k8s")]
+pub fn apply(
+    objects: &[Value],
+    namespace: Option<&str>,
+    field_manager: &str,
+    force_conflicts: bool,
+    dry_run: bool,
+) -> Result<Vec<String>, String> {
+    native::apply(objects, namespace, field_manager, force_conflicts, dry_run)
+}
+
+#[cfg(not(feature = "native-k8s"))]
+pub fn apply(
+    _objects: &[Value],
+    _namespace: Option<&str>,
+    _field_manager: &str,
+    _force_conflicts: bool,
+    _dry_run: bool,
+) -> Result<Vec<String>, String> {
+    Err("helmctl was built without the native-k8s feature; install kubectl".to_string())
+}
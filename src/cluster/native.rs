@@ -0,0 +1,196 @@
+//! API server access through the kube client, used when helmctl is built
+//! with the `native-k8s` feature.
+
+use super::{selected_context, Probe, Resource};
+use k8s_openapi::api::core::v1::{Event, Namespace, Pod, Secret};
+use k8s_openapi::NamespaceResourceScope;
+use kube::api::{Api, DynamicObject, ListParams, Patch, PatchParams};
+use kube::config::KubeConfigOptions;
+use kube::core::GroupVersionKind;
+use kube::discovery::{self, Scope};
+use kube::{Client, Config};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt::Debug;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("failed to start async runtime")
+        .block_on(future)
+}
+
+async fn client() -> Result<Client, String> {
+    client_for(selected_context(), None).await
+}
+
+async fn client_for(context: Option<&str>, timeout: Option<Duration>) -> Result<Client, String> {
+    let mut config = match context {
+        Some(context) => {
+            let options = KubeConfigOptions {
+                context: Some(context.to_string()),
+                ..Default::default()
+            };
+            Config::from_kubeconfig(&options)
+                .await
+                .map_err(|e| e.to_string())
+        }
+        None => Config::infer().await.map_err(|e| e.to_string()),
+    }
+    .map_err(|e| format!("Failed to load Kubernetes configuration: {}", e))?;
+    if timeout.is_some() {
+        config.connect_timeout = timeout;
+        config.read_timeout = timeout;
+    }
+
+    Client::try_from(config).map_err(|e| format!("Failed to create Kubernetes client: {}", e))
+}
+
+pub fn list(
+    resource: Resource,
+    namespace: Option<&str>,
+    labels: Option<&str>,
+    fields: Option<&str>,
+) -> Result<Vec<Value>, String> {
+    block_on(async {
+        let client = client().await?;
+
+        let mut params = ListParams::default();
+        if let Some(labels) = labels {
+            params = params.labels(labels);
+        }
+        if let Some(fields) = fields {
+            params = params.fields(fields);
+        }
+
+        match resource {
+            Resource::Pods => list_typed::<Pod>(client, namespace, &params).await,
+            Resource::Secrets => list_typed::<Secret>(client, namespace, &params).await,
+            Resource::Events => list_typed::<Event>(client, namespace, &params).await,
+        }
+    })
+}
+
+async fn list_typed<K>(
+    client: Client,
+    namespace: Option<&str>,
+    params: &ListParams,
+) -> Result<Vec<Value>, String>
+where
+    K: kube::Resource<Scope = NamespaceResourceScope>
+        + Clone
+        + DeserializeOwned
+        + Serialize
+        + Debug,
+    K::DynamicType: Default,
+{
+    let api: Api<K> = match namespace {
+        Some(ns) => Api::namespaced(client, ns),
+        None => Api::default_namespaced(client),
+    };
+
+    let list = api
+        .list(params)
+        .await
+        .map_err(|e| format!("Failed to list {}: {}", K::plural(&Default::default()), e))?;
+
+    list.items
+        .iter()
+        .map(|item| serde_json::to_value(item).map_err(|e| e.to_string()))
+        .collect()
+}
+
+pub fn server_version() -> Result<String, String> {
+    block_on(async {
+        let info = client()
+            .await?
+            .apiserver_version()
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(info.git_version)
+    })
+}
+
+pub fn probe(context: &str, namespace: &str, timeout: Duration) -> Result<Probe, String> {
+    block_on(async {
+        let client = client_for(Some(context), Some(timeout)).await?;
+        let start = Instant::now();
+        let info = client
+            .apiserver_version()
+            .await
+            .map_err(|e| e.to_string())?;
+        let latency = start.elapsed();
+
+        let pods: Api<Pod> = Api::namespaced(client, namespace);
+        pods.list(&ListParams::default().limit(1))
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(Probe {
+            version: info.git_version,
+            latency,
+        })
+    })
+}
+
+pub fn namespace_exists(name: &str) -> Result<bool, String> {
+    block_on(async {
+        let api: Api<Namespace> = Api::all(client().await?);
+        api.get_opt(name)
+            .await
+            .map(|namespace| namespace.is_some())
+            .map_err(|e| format!("Failed to look up namespace {}: {}", name, e))
+    })
+}
+
+pub fn apply(
+    objects: &[Value],
+    namespace: Option<&str>,
+    field_manager: &str,
+    force_conflicts: bool,
+    dry_run: bool,
+) -> Result<Vec<String>, String> {
+    block_on(async {
+        let client = client().await?;
+
+        let mut params = PatchParams::apply(field_manager);
+        if force_conflicts {
+            params = params.force();
+        }
+        params.dry_run = dry_run;
+
+        let mut applied = Vec::new();
+        for object in objects {
+            let api_version = object["apiVersion"].as_str().unwrap_or_default();
+            let kind = object["kind"].as_str().unwrap_or_default();
+            let name = object["metadata"]["name"]
+                .as_str()
+                .ok_or_else(|| format!("{} without metadata.name", kind))?;
+
+            let (group, version) = api_version.split_once('/').unwrap_or(("", api_version));
+            let gvk = GroupVersionKind::gvk(group, version, kind);
+            let (resource, capabilities) = discovery::pinned_kind(&client, &gvk)
+                .await
+                .map_err(|e| format!("Unknown resource {} {}: {}", api_version, kind, e))?;
+
+            let api: Api<DynamicObject> = match capabilities.scope {
+                Scope::Cluster => Api::all_with(client.clone(), &resource),
+                Scope::Namespaced => match object["metadata"]["namespace"].as_str().or(namespace) {
+                    Some(ns) => Api::namespaced_with(client.clone(), ns, &resource),
+                    None => Api::default_namespaced_with(client.clone(), &resource),
+                },
+            };
+
+            api.patch(name, &params, &Patch::Apply(object))
+                .await
+                .map_err(|e| format!("Failed to apply {}/{}: {}", kind, name, e))?;
+            applied.push(format!("{}/{}", kind.to_lowercase(), name));
+        }
+
+        Ok(applied)
+    })
+}
@@ -0,0 +1,168 @@
+use crate::ci::{self, say};
+use crate::cli::NoiseFilter;
+use crate::commands::validate;
+use crate::diff;
+use crate::redact::redact;
+use crate::utils::print_diff_line;
+use colored::*;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// Rendered objects by release name, as returned by
+/// [`validate::render_releases`].
+pub type Releases = BTreeMap<String, Vec<Value>>;
+
+/// Renders `file` for two environments and shows how they differ.
+/// Returns whether any difference was found.
+pub fn execute(
+    file: &str,
+    environments: &[String],
+    ignore: &[NoiseFilter],
+    verbose: bool,
+) -> Result<bool, String> {
+    let [before, after] = environments else {
+        return Err("compare needs exactly two environments, e.g. -e staging -e prod".to_string());
+    };
+
+    say!(
+        "{}",
+        format!("🔍 Comparing {} between {} and {}", file, before, after)
+            .cyan()
+            .bold()
+    );
+
+    if !Path::new(file).exists() {
+        return Err(format!("Helmfile not found: {}", file));
+    }
+
+    let old = validate::render_releases(file, Some(before), verbose)?;
+    let new = validate::render_releases(file, Some(after), verbose)?;
+
+    Ok(report(&old, &new, (before, after), ignore))
+}
+
+/// Prints a per-release, per-object diff of two renders, labelling the
+/// sides with `labels`. Returns whether they differ.
+pub fn report(
+    old: &Releases,
+    new: &Releases,
+    labels: (&str, &str),
+    ignore: &[NoiseFilter],
+) -> bool {
+    let names: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    let mut changed_releases = 0;
+    // Markdown for the CI job summary, one section per changed release
+    let mut sections = Vec::new();
+
+    for name in names {
+        let (before, after) = match (old.get(name), new.get(name)) {
+            (Some(before), Some(after)) => (before, after),
+            (Some(_), None) => {
+                sections.push(format!("`{}` only in `{}`\n", name, labels.0));
+                say!(
+                    "\n📦 {} {}",
+                    name.bold(),
+                    format!("only in {}", labels.0).red()
+                );
+                changed_releases += 1;
+                continue;
+            }
+            (None, Some(_)) => {
+                sections.push(format!("`{}` only in `{}`\n", name, labels.1));
+                say!(
+                    "\n📦 {} {}",
+                    name.bold(),
+                    format!("only in {}", labels.1).green()
+                );
+                changed_releases += 1;
+                continue;
+            }
+            (None, None) => continue,
+        };
+
+        let before = index(before, ignore);
+        let after = index(after, ignore);
+        let mut lines = Vec::new();
+
+        let keys: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+        for key in keys {
+            // Like diff(1), a missing side is labelled /dev/null
+            let side = |objects: &BTreeMap<String, String>, label: &str| match objects.get(key) {
+                Some(text) => (text.clone(), format!("{}: {}", label, key)),
+                None => (String::new(), "/dev/null".to_string()),
+            };
+            let (old_text, old_label) = side(&before, labels.0);
+            let (new_text, new_label) = side(&after, labels.1);
+            lines.extend(diff::unified(&old_text, &new_text, &old_label, &new_label));
+        }
+
+        if !lines.is_empty() {
+            changed_releases += 1;
+            say!("\n📦 {}", name.bold());
+            lines.iter().for_each(|line| print_diff_line(line));
+            sections.push(format!(
+                "<details><summary><code>{}</code></summary>\n\n{}\n\n</details>\n",
+                name,
+                ci::code_block("diff", &lines.join("\n"))
+            ));
+        }
+    }
+
+    println!();
+    let verdict = if changed_releases == 0 {
+        "✅ No differences in rendered manifests".to_string()
+    } else {
+        format!("📊 {} release(s) differ", changed_releases)
+    };
+    if changed_releases == 0 {
+        say!("{}", verdict.green());
+    } else {
+        say!("{}", verdict);
+    }
+
+    ci::summary(&format!(
+        "## helmctl: `{}` → `{}`\n\n{}\n\n{}",
+        labels.0,
+        labels.1,
+        verdict,
+        sections.join("\n")
+    ));
+    changed_releases > 0
+}
+
+/// Objects of one release as redacted YAML, keyed by `kind/name` (plus
+/// the namespace unless namespaces are ignored).
+fn index(objects: &[Value], ignore: &[NoiseFilter]) -> BTreeMap<String, String> {
+    objects
+        .iter()
+        .map(|object| {
+            let mut object = object.clone();
+            for filter in ignore {
+                filter.apply(&mut object);
+            }
+
+            let kind = object["kind"].as_str().unwrap_or_default().to_lowercase();
+            let name = object["metadata"]["name"].as_str().unwrap_or_default();
+            let key = match object["metadata"]["namespace"].as_str() {
+                Some(ns) => format!("{}/{}/{}", ns, kind, name),
+                None => format!("{}/{}", kind, name),
+            };
+            let text = serde_yaml::to_string(&object).unwrap_or_default();
+            (key, redact(&text))
+        })
+        .collect()
+}
+
+impl NoiseFilter {
+    /// Removes the fields this filter ignores from a rendered object.
+    fn apply(self, object: &mut Value) {
+        let (parent, field) = match self {
+            NoiseFilter::Namespaces => ("metadata", "namespace"),
+            NoiseFilter::Replicas => ("spec", "replicas"),
+        };
+        if let Some(map) = object.get_mut(parent).and_then(Value::as_object_mut) {
+            map.remove(field);
+        }
+    }
+}
@@ -1,23 +1,90 @@
-use crate::cli::ConfigAction;
-use crate::config::Config;
+use crate::ci::say;
+use crate::cli::{ConfigAction, RepoAction};
+use crate::config::{Config, Repository};
+use crate::redact::redact;
 use colored::*;
 
-pub fn execute(action: ConfigAction, config_path: &str) -> Result<(), String> {
+/// Runs a config subcommand. `explicit` is the `--config` flag; reads use
+/// the merged layers while writes go to the project file.
+pub fn execute(action: ConfigAction, explicit: Option<&str>) -> Result<(), String> {
+    let project_path = Config::project_path(explicit);
+    let config_path = project_path.to_string_lossy();
+
     match action {
-        ConfigAction::Show => show_config(config_path),
-        ConfigAction::Init => init_config(config_path),
-        ConfigAction::Set { key, value } => set_config_value(config_path, &key, &value),
-        ConfigAction::Get { key } => get_config_value(config_path, &key),
+        ConfigAction::Show { origin } => show_config(explicit, origin),
+        ConfigAction::Init => init_config(&config_path),
+        ConfigAction::Set { key, value } => set_config_value(&config_path, &key, &value),
+        ConfigAction::Get { key } => get_config_value(explicit, &key),
+        ConfigAction::Unset { key } => unset_config_value(&config_path, &key),
+        ConfigAction::Repo { action } => repo_command(action, explicit, &config_path),
+        ConfigAction::Validate => validate_config(explicit),
+        ConfigAction::Schema => print_schema(),
     }
 }
 
-fn show_config(config_path: &str) -> Result<(), String> {
-    let config = Config::load(config_path)?;
-    let yaml =
-        serde_yaml::to_string(&config).map_err(|e| format!("Failed to serialize config: {}", e))?;
+fn validate_config(explicit: Option<&str>) -> Result<(), String> {
+    say!("{}", "🔍 Validating configuration layers:".cyan().bold());
+
+    let mut issue_count = 0;
+    for layer in Config::layers(explicit) {
+        if !layer.path.exists() {
+            continue;
+        }
+
+        let issues = Config::validate_file(&layer.path.to_string_lossy())?;
+        issue_count += issues.len();
+
+        if issues.is_empty() {
+            say!("  ✅ {} ({})", layer.name, layer.path.display());
+        } else {
+            say!("  ❌ {} ({})", layer.name, layer.path.display());
+            for issue in &issues {
+                println!("      {}", issue);
+            }
+        }
+    }
+
+    // Environment variables are validated as part of resolution
+    if let Err(e) = Config::resolve(explicit) {
+        if issue_count == 0 {
+            say!("  ❌ {}", e);
+            issue_count += 1;
+        }
+    }
+
+    if issue_count == 0 {
+        say!("{}", "✅ Configuration is valid".green());
+        return Ok(());
+    }
+
+    Err(format!("Found {} configuration issue(s)", issue_count))
+}
+
+fn print_schema() -> Result<(), String> {
+    let schema = serde_json::to_string_pretty(&Config::schema())
+        .map_err(|e| format!("Failed to serialize schema: {}", e))?;
+    println!("{}", schema);
+    Ok(())
+}
+
+fn show_config(explicit: Option<&str>, origin: bool) -> Result<(), String> {
+    let resolved = Config::resolve(explicit)?;
+    let yaml = serde_yaml::to_string(&resolved.config)
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+
+    say!("{}", "📋 Current configuration:".cyan().bold());
+    println!("{}", redact(&yaml));
+
+    if origin {
+        say!("{}", "📍 Value origins:".cyan().bold());
+        if resolved.origins.is_empty() {
+            println!("  All values are built-in defaults");
+        }
+        for (key, source) in &resolved.origins {
+            println!("  {:<28} {}", key.yellow(), source);
+        }
+    }
 
-    println!("{}", "📋 Current configuration:".cyan().bold());
-    println!("{}", yaml);
     Ok(())
 }
 
@@ -25,7 +92,7 @@ fn init_config(config_path: &str) -> Result<(), String> {
     let config = Config::default_config();
     config.save(config_path)?;
 
-    println!("{}", "✅ Configuration initialized with defaults:".green());
+    say!("{}", "✅ Configuration initialized with defaults:".green());
     println!("  • Default environment: development");
     println!("  • Default concurrency: 2");
     println!("  • Default timeout: 300s");
@@ -40,80 +107,108 @@ fn init_config(config_path: &str) -> Result<(), String> {
 
 fn set_config_value(config_path: &str, key: &str, value: &str) -> Result<(), String> {
     let mut config = Config::load(config_path)?;
+    config.set_value(key, value)?;
+    config.save(config_path)?;
 
-    match key {
-        "default_environment" => {
-            config.default_environment = Some(value.to_string());
-        }
-        "default_concurrency" => {
-            let parsed: u8 = value
-                .parse()
-                .map_err(|_| "Invalid concurrency value (must be 1-255)")?;
-            config.default_concurrency = Some(parsed);
-        }
-        "default_timeout" => {
-            let parsed: u32 = value
-                .parse()
-                .map_err(|_| "Invalid timeout value (must be a positive number)")?;
-            config.default_timeout = Some(parsed);
-        }
-        "auto_update_repos" => {
-            let parsed: bool = value
-                .parse()
-                .map_err(|_| "Invalid boolean value (use 'true' or 'false')")?;
-            config.auto_update_repos = Some(parsed);
-        }
-        "preferred_context" => {
-            config.preferred_context = Some(value.to_string());
+    say!("{} Set {} = {}", "✅".green(), key.cyan(), value.yellow());
+    Ok(())
+}
+
+fn unset_config_value(config_path: &str, key: &str) -> Result<(), String> {
+    let mut config = Config::load(config_path)?;
+    config.unset_value(key)?;
+    config.save(config_path)?;
+
+    say!("{} Unset {}", "✅".green(), key.cyan());
+    Ok(())
+}
+
+fn get_config_value(explicit: Option<&str>, key: &str) -> Result<(), String> {
+    let config = Config::resolve(explicit)?.config;
+
+    match config.get_value(key)? {
+        serde_yaml::Value::Null => println!("{}: {}", key.cyan(), "not set".yellow()),
+        serde_yaml::Value::String(value) => println!("{}: {}", key.cyan(), value.yellow()),
+        value @ (serde_yaml::Value::Bool(_) | serde_yaml::Value::Number(_)) => {
+            let rendered = serde_yaml::to_string(&value)
+                .map_err(|e| format!("Failed to serialize value: {}", e))?;
+            println!("{}: {}", key.cyan(), rendered.trim_end().yellow());
         }
-        "log_level" => match value.to_lowercase().as_str() {
-            "debug" | "info" | "warn" | "error" => {
-                config.log_level = Some(value.to_lowercase());
+        value => {
+            let rendered = serde_yaml::to_string(&value)
+                .map_err(|e| format!("Failed to serialize value: {}", e))?;
+            println!("{}:", key.cyan());
+            for line in rendered.lines() {
+                println!("  {}", line);
             }
-            _ => return Err("Invalid log level (use: debug, info, warn, error)".to_string()),
-        },
-        _ => {
-            return Err(format!(
-                "Unknown configuration key: {}. Available keys: default_environment, default_concurrency, default_timeout, auto_update_repos, preferred_context, log_level", 
-                key
-            ));
         }
     }
 
-    config.save(config_path)?;
-    println!("{} Set {} = {}", "✅".green(), key.cyan(), value.yellow());
-
     Ok(())
 }
 
-fn get_config_value(config_path: &str, key: &str) -> Result<(), String> {
-    let config = Config::load(config_path)?;
-
-    let value = match key {
-        "default_environment" => config
-            .default_environment
-            .unwrap_or_else(|| "not set".to_string()),
-        "default_concurrency" => config
-            .default_concurrency
-            .map_or("not set".to_string(), |v| v.to_string()),
-        "default_timeout" => config
-            .default_timeout
-            .map_or("not set".to_string(), |v| v.to_string()),
-        "auto_update_repos" => config
-            .auto_update_repos
-            .map_or("not set".to_string(), |v| v.to_string()),
-        "preferred_context" => config
-            .preferred_context
-            .unwrap_or_else(|| "not set".to_string()),
-        "log_level" => config.log_level.unwrap_or_else(|| "not set".to_string()),
-        _ => {
-            return Err(format!(
-                "Unknown configuration key: {}. Available keys: default_environment, default_concurrency, default_timeout, auto_update_repos, preferred_context, log_level", 
-                key
-            ));
+fn repo_command(
+    action: RepoAction,
+    explicit: Option<&str>,
+    config_path: &str,
+) -> Result<(), String> {
+    match action {
+        RepoAction::List => {
+            let config = Config::resolve(explicit)?.config;
+            let repositories = config.repositories.unwrap_or_default();
+            if repositories.is_empty() {
+                println!("No repositories configured");
+                return Ok(());
+            }
+
+            say!("{}", "📦 Configured repositories:".cyan().bold());
+            for repo in &repositories {
+                println!("  {:<20} {}", repo.name.yellow(), redact(&repo.url));
+            }
+            Ok(())
         }
-    };
+        RepoAction::Add { name, url, force } => {
+            let mut config = Config::load(config_path)?;
+            let repositories = config.repositories.get_or_insert_with(Vec::new);
 
-    println!("{}: {}", key.cyan(), value.yellow());
-    Ok(())
+            match repositories.iter_mut().find(|r| r.name == name) {
+                Some(existing) if force => existing.url = url.clone(),
+                Some(existing) => {
+                    return Err(format!(
+                        "Repository {} already exists ({}). Use --force to replace it",
+                        name,
+                        redact(&existing.url)
+                    ))
+                }
+                None => repositories.push(Repository {
+                    name: name.clone(),
+                    url: url.clone(),
+                }),
+            }
+
+            config.validate()?;
+            config.save(config_path)?;
+            say!(
+                "{} Added repository {} ({})",
+                "✅".green(),
+                name.cyan(),
+                redact(&url).yellow()
+            );
+            Ok(())
+        }
+        RepoAction::Remove { name } => {
+            let mut config = Config::load(config_path)?;
+            let repositories = config.repositories.get_or_insert_with(Vec::new);
+
+            let before = repositories.len();
+            repositories.retain(|r| r.name != name);
+            if repositories.len() == before {
+                return Err(format!("Repository not found: {}", name));
+            }
+
+            config.save(config_path)?;
+            say!("{} Removed repository {}", "✅".green(), name.cyan());
+            Ok(())
+        }
+    }
 }
@@ -1,155 +1,670 @@
+use crate::ci::{esay, say};
 use crate::cli::ContextAction;
-use crate::utils::check_command_available;
+use crate::cluster;
+use crate::config::Config;
+use crate::kubeconfig::{self, Kubeconfig};
+use crate::tools;
 use colored::*;
-use std::process::Command;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
 
-pub fn execute(action: ContextAction) -> Result<(), String> {
-    check_command_available("kubectl")?;
+/// Number of contexts kept in the history file.
+const HISTORY_LIMIT: usize = 10;
+
+/// Runs a context subcommand. Aliases are read from the merged `config`;
+/// `explicit` is the `--config` flag and selects the file aliases are
+/// written to.
+pub fn execute(
+    action: ContextAction,
+    config: &Config,
+    explicit: Option<&str>,
+) -> Result<(), String> {
+    let aliases = config.context_aliases.clone().unwrap_or_default();
 
     match action {
-        ContextAction::List => list_contexts(),
-        ContextAction::Use { context } => use_context(&context),
+        ContextAction::List => list_contexts(&aliases),
+        ContextAction::Use { context } => use_context(&context, &aliases),
         ContextAction::Current => show_current_context(),
+        ContextAction::Info { context } => match context {
+            Some(query) => {
+                let kubeconfig = Kubeconfig::load()?;
+                let context = resolve(&kubeconfig, &aliases, &query)?;
+                show_context_info(&kubeconfig, &context)
+            }
+            None => show_current_context(),
+        },
+        ContextAction::Alias {
+            name,
+            context,
+            remove,
+        } => alias_command(name, context, remove, &aliases, explicit),
+        ContextAction::History => show_history(),
+        ContextAction::Env { context } => {
+            let context = resolve(&Kubeconfig::load()?, &aliases, &context)?;
+            print_env(&context)
+        }
+        ContextAction::Shell { context } => {
+            let context = resolve(&Kubeconfig::load()?, &aliases, &context)?;
+            run_shell(&context)
+        }
+        ContextAction::Namespace { namespace, context } => {
+            namespace_command(namespace.as_deref(), context.as_deref(), &aliases)
+        }
+        ContextAction::Check {
+            context,
+            all,
+            timeout,
+            optional,
+        } => check_contexts(context.as_deref(), all, timeout, &optional, &aliases),
     }
 }
 
-fn list_contexts() -> Result<(), String> {
-    println!("{}", "📋 Available Kubernetes contexts:".cyan().bold());
+fn list_contexts(aliases: &BTreeMap<String, String>) -> Result<(), String> {
+    say!("{}", "📋 Available Kubernetes contexts:".cyan().bold());
 
-    let output = Command::new("kubectl")
-        .args(["config", "get-contexts"])
-        .output()
-        .map_err(|e| format!("Failed to get contexts: {}", e))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Failed to list contexts:\n{}", stderr));
+    let kubeconfig = Kubeconfig::load()?;
+    if kubeconfig.contexts.is_empty() {
+        println!("  No contexts found in {}", describe_paths());
+        return Ok(());
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
+    let rows: Vec<[&str; 5]> = kubeconfig
+        .contexts
+        .iter()
+        .map(|c| {
+            [
+                c.name.as_str(),
+                c.cluster.as_str(),
+                c.server.as_deref().unwrap_or("-"),
+                c.user.as_deref().unwrap_or("-"),
+                c.namespace.as_deref().unwrap_or("default"),
+            ]
+        })
+        .collect();
+
+    let header = ["NAME", "CLUSTER", "SERVER", "USER", "NAMESPACE"];
+    let mut widths = header.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+    let format_row = |marker: &str, row: &[&str; 5]| {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        format!("{:<2}{}", marker, cells.join("  "))
+            .trim_end()
+            .to_string()
+    };
 
-    // Parse and colorize the output
-    for (i, line) in stdout.lines().enumerate() {
-        if i == 0 {
-            // Header line
-            println!("{}", line.bright_white().bold());
-        } else if line.contains('*') {
-            // Current context (marked with *)
-            println!("{}", line.green().bold());
+    println!("{}", format_row("", &header).bright_white().bold());
+    for (context, row) in kubeconfig.contexts.iter().zip(&rows) {
+        if kubeconfig.current_context.as_deref() == Some(context.name.as_str()) {
+            println!("{}", format_row("*", row).green().bold());
         } else {
-            // Other contexts
-            println!("{}", line);
+            println!("{}", format_row("", row));
+        }
+    }
+
+    if !aliases.is_empty() {
+        println!();
+        println!("{}", "Aliases:".bright_white().bold());
+        for (alias, context) in aliases {
+            println!("  {:<20} → {}", alias.yellow(), context);
         }
     }
 
     Ok(())
 }
 
-fn use_context(context: &str) -> Result<(), String> {
-    println!(
+fn use_context(query: &str, aliases: &BTreeMap<String, String>) -> Result<(), String> {
+    let kubeconfig = Kubeconfig::load()?;
+    let previous = kubeconfig.current_context.clone();
+
+    let context = if query == "-" {
+        read_history()
+            .into_iter()
+            .find(|c| Some(c) != previous.as_ref() && kubeconfig.context(c).is_some())
+            .ok_or("No previous context in the history")?
+    } else {
+        resolve(&kubeconfig, aliases, query)?
+    };
+    let context = context.as_str();
+    if context != query && !aliases.contains_key(query) && query != "-" {
+        say!("🔎 {} matches context {}", query.yellow(), context.cyan());
+    }
+
+    say!(
         "{}",
         format!("🔄 Switching to context: {}", context)
             .cyan()
             .bold()
     );
 
-    let status = Command::new("kubectl")
-        .args(["config", "use-context", context])
-        .status()
-        .map_err(|e| format!("Failed to switch context: {}", e))?;
+    let file = kubeconfig::set_current_context(context)?;
+    record_history(previous.as_deref(), context);
+    say!(
+        "{}",
+        format!("✅ Switched to context: {} ({})", context, file.display()).green()
+    );
 
-    if !status.success() {
-        return Err(format!("Failed to switch to context: {}", context));
-    }
+    // Show some basic info about the new context
+    show_context_info(&kubeconfig, context)
+}
+
+fn show_current_context() -> Result<(), String> {
+    say!("{}", "📋 Current Kubernetes context:".cyan().bold());
 
-    println!("{}", format!("✅ Switched to context: {}", context).green());
+    let kubeconfig = Kubeconfig::load()?;
+    let context = kubeconfig
+        .current_context
+        .clone()
+        .ok_or_else(|| format!("No current context set in {}", describe_paths()))?;
+    println!("{}", context.yellow().bold());
 
-    // Show some basic info about the new context
-    show_context_info(context)?;
+    // Show additional context info
+    show_context_info(&kubeconfig, &context)
+}
+
+fn show_context_info(kubeconfig: &Kubeconfig, context: &str) -> Result<(), String> {
+    let entry = kubeconfig.context(context).ok_or_else(|| {
+        format!(
+            "Context not found: {}. Available contexts: {}",
+            context,
+            context_names(kubeconfig)
+        )
+    })?;
+
+    say!("\n{}", format!("🔍 Context details: {}", context).yellow());
+    println!("  Cluster: {}", entry.cluster);
+    println!("  Server: {}", entry.server.as_deref().unwrap_or("-"));
+    println!("  User: {}", entry.user.as_deref().unwrap_or("-"));
+    println!(
+        "  Namespace: {}",
+        entry.namespace.as_deref().unwrap_or("default")
+    );
+
+    // Test connectivity of the requested context, not the current one
+    print!("  Connectivity: ");
+    if tools::resolve("kubectl").is_none() && !cluster::native_available() {
+        say!("{}", "⏭️  skipped, kubectl is not available".yellow());
+        return Ok(());
+    }
+    cluster::select_context(context);
+    match cluster::server_version() {
+        Ok(version) => say!("{} (server {})", "✅ Connected".green(), version),
+        Err(_) => say!("{}", "❌ Connection failed".red()),
+    }
 
     Ok(())
 }
 
-fn show_current_context() -> Result<(), String> {
-    println!("{}", "📋 Current Kubernetes context:".cyan().bold());
+/// Resolves what the user typed to a context name. Exact names win, then
+/// aliases, then a unique prefix, substring or fuzzy (in-order characters)
+/// match, all case-insensitive.
+fn resolve(
+    kubeconfig: &Kubeconfig,
+    aliases: &BTreeMap<String, String>,
+    query: &str,
+) -> Result<String, String> {
+    if kubeconfig.context(query).is_some() {
+        return Ok(query.to_string());
+    }
 
-    let output = Command::new("kubectl")
-        .args(["config", "current-context"])
-        .output()
-        .map_err(|e| format!("Failed to get current context: {}", e))?;
+    if let Some(target) = aliases.get(query) {
+        if kubeconfig.context(target).is_none() {
+            return Err(format!(
+                "Alias {} points to unknown context: {}",
+                query, target
+            ));
+        }
+        return Ok(target.clone());
+    }
+
+    let query_lower = query.to_lowercase();
+    let matchers: [fn(&str, &str) -> bool; 3] = [
+        |name, query| name.starts_with(query),
+        |name, query| name.contains(query),
+        is_subsequence,
+    ];
+
+    for matches in matchers {
+        let candidates: Vec<&str> = kubeconfig
+            .contexts
+            .iter()
+            .map(|c| c.name.as_str())
+            .filter(|name| matches(&name.to_lowercase(), &query_lower))
+            .collect();
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Failed to get current context:\n{}", stderr));
+        match candidates.as_slice() {
+            [] => continue,
+            [name] => return Ok(name.to_string()),
+            _ => {
+                return Err(format!(
+                    "Ambiguous context {}, it matches: {}",
+                    query,
+                    candidates.join(", ")
+                ))
+            }
+        }
     }
 
-    let stdout_str = String::from_utf8_lossy(&output.stdout);
-    let stdout = stdout_str.trim();
-    println!("{}", stdout.yellow().bold());
+    Err(format!(
+        "Context not found: {}. Available contexts: {}",
+        query,
+        context_names(kubeconfig)
+    ))
+}
 
-    // Show additional context info
-    show_context_info(stdout)?;
+fn is_subsequence(name: &str, query: &str) -> bool {
+    let mut chars = name.chars();
+    query.chars().all(|q| chars.any(|c| c == q))
+}
+
+fn alias_command(
+    name: Option<String>,
+    context: Option<String>,
+    remove: bool,
+    aliases: &BTreeMap<String, String>,
+    explicit: Option<&str>,
+) -> Result<(), String> {
+    let Some(name) = name else {
+        if aliases.is_empty() {
+            println!("No context aliases configured");
+            return Ok(());
+        }
+        say!("{}", "🏷️  Context aliases:".cyan().bold());
+        for (alias, context) in aliases {
+            println!("  {:<20} → {}", alias.yellow(), context);
+        }
+        return Ok(());
+    };
 
+    let config_path = Config::project_path(explicit);
+    let config_path = config_path.to_string_lossy();
+    let mut stored = Config::load(&config_path)?
+        .context_aliases
+        .unwrap_or_default();
+
+    if remove {
+        if stored.remove(&name).is_none() {
+            return Err(format!("Alias not found in {}: {}", config_path, name));
+        }
+        save_aliases(&config_path, stored)?;
+        say!("{} Removed alias {}", "✅".green(), name.cyan());
+        return Ok(());
+    }
+
+    let context = context.ok_or("Missing the context the alias points to")?;
+    if Kubeconfig::load()?.context(&context).is_none() {
+        say!(
+            "⚠️  Context {} is not in the current kubeconfig",
+            context.yellow()
+        );
+    }
+
+    stored.insert(name.clone(), context.clone());
+    save_aliases(&config_path, stored)?;
+    say!(
+        "{} Added alias {} → {}",
+        "✅".green(),
+        name.cyan(),
+        context.yellow()
+    );
     Ok(())
 }
 
-fn show_context_info(_context: &str) -> Result<(), String> {
-    // Get cluster info
-    println!("\n{}", "🔍 Context details:".yellow());
-
-    let output = Command::new("kubectl")
-        .args([
-            "config",
-            "view",
-            "--minify",
-            "--output",
-            "jsonpath={.clusters[0].cluster.server}",
-        ])
-        .output()
-        .map_err(|e| format!("Failed to get cluster info: {}", e))?;
-
-    if output.status.success() {
-        let server = String::from_utf8_lossy(&output.stdout);
-        if !server.is_empty() {
-            println!("  Server: {}", server.trim());
-        }
-    }
-
-    // Get current namespace
-    let ns_output = Command::new("kubectl")
-        .args([
-            "config",
-            "view",
-            "--minify",
-            "--output",
-            "jsonpath={.contexts[0].context.namespace}",
-        ])
-        .output()
-        .map_err(|e| format!("Failed to get namespace: {}", e))?;
-
-    if ns_output.status.success() {
-        let namespace = String::from_utf8_lossy(&ns_output.stdout);
-        if !namespace.trim().is_empty() {
-            println!("  Namespace: {}", namespace.trim());
+/// Rewrites only `context_aliases` in the project config file.
+fn save_aliases(config_path: &str, aliases: BTreeMap<String, String>) -> Result<(), String> {
+    let value = (!aliases.is_empty())
+        .then(|| serde_yaml::to_value(aliases))
+        .transpose()
+        .map_err(|e| format!("Failed to serialize aliases: {}", e))?;
+    Config::update_key(config_path, "context_aliases", value)
+}
+
+fn show_history() -> Result<(), String> {
+    let history = read_history();
+    if history.is_empty() {
+        println!("No contexts used yet");
+        return Ok(());
+    }
+
+    let current = Kubeconfig::load()?.current_context;
+    say!("{}", "🕘 Recently used contexts:".cyan().bold());
+    for context in &history {
+        if current.as_ref() == Some(context) {
+            println!("  {} {}", "*".green().bold(), context.green().bold());
         } else {
-            println!("  Namespace: default");
+            println!("    {}", context);
         }
     }
+    Ok(())
+}
 
-    // Test connectivity
-    print!("  Connectivity: ");
-    let test_output = Command::new("kubectl")
-        .args(["cluster-info", "--request-timeout=5s"])
-        .output();
+fn history_file() -> Option<PathBuf> {
+    let state = match env::var("XDG_STATE_HOME") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => Path::new(&env::var("HOME").ok()?)
+            .join(".local")
+            .join("state"),
+    };
+    Some(state.join("helmctl").join("context-history"))
+}
+
+/// Recently used contexts, most recent first.
+fn read_history() -> Vec<String> {
+    history_file()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|content| content.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// Records a switch from `previous` to `context`. The history is a
+/// convenience, so failures to write it are ignored.
+fn record_history(previous: Option<&str>, context: &str) {
+    let Some(path) = history_file() else {
+        return;
+    };
+
+    let mut history = read_history();
+    for entry in previous.into_iter().chain([context]) {
+        history.retain(|c| c != entry);
+        history.insert(0, entry.to_string());
+    }
+    history.truncate(HISTORY_LIMIT);
+
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let _ = fs::write(path, history.join("\n") + "\n");
+}
+
+/// Shows the default namespace of a context or, given `namespace`, makes
+/// it the default after checking that it exists in the cluster.
+fn namespace_command(
+    namespace: Option<&str>,
+    context: Option<&str>,
+    aliases: &BTreeMap<String, String>,
+) -> Result<(), String> {
+    let kubeconfig = Kubeconfig::load()?;
+    let context = match context {
+        Some(query) => resolve(&kubeconfig, aliases, query)?,
+        None => kubeconfig
+            .current_context
+            .clone()
+            .ok_or_else(|| format!("No current context set in {}", describe_paths()))?,
+    };
+    let entry = kubeconfig
+        .context(&context)
+        .ok_or_else(|| format!("Context not found: {}", context))?;
+
+    let Some(namespace) = namespace else {
+        say!(
+            "📦 Namespace of {}: {}",
+            context.cyan(),
+            entry.namespace.as_deref().unwrap_or("default").yellow()
+        );
+        return Ok(());
+    };
+
+    say!(
+        "🔍 Checking namespace {} in {}",
+        namespace.yellow(),
+        context.cyan()
+    );
+    if tools::resolve("kubectl").is_none() && !cluster::native_available() {
+        return Err("Cannot check the namespace: kubectl is not installed".to_string());
+    }
+    cluster::select_context(&context);
+    match cluster::namespace_exists(namespace) {
+        Ok(true) => {}
+        Ok(false) => {
+            return Err(format!(
+                "Namespace {} does not exist in context {}",
+                namespace, context
+            ))
+        }
+        // Namespace-scoped credentials may not be allowed to read namespaces
+        Err(e) if e.to_lowercase().contains("forbidden") => {
+            say!("⚠️  Cannot verify the namespace: {}", e);
+        }
+        Err(e) => return Err(e),
+    }
+
+    let file = kubeconfig::set_namespace(&context, namespace)?;
+    say!(
+        "{}",
+        format!(
+            "✅ Default namespace of {} set to {} ({})",
+            context,
+            namespace,
+            file.display()
+        )
+        .green()
+    );
+    Ok(())
+}
 
-    match test_output {
-        Ok(output) if output.status.success() => {
-            println!("{}", "✅ Connected".green());
+/// Failure classes recognised in API client errors, checked in order. Exec
+/// plugin failures come first because their messages quote the underlying
+/// cause, which may look like any of the others.
+const FAILURE_CLASSES: [(&str, &[&str]); 6] = [
+    (
+        "exec plugin failed",
+        &["getting credentials", "exec plugin", "exec:"],
+    ),
+    (
+        "DNS lookup failed",
+        &[
+            "no such host",
+            "name or service not known",
+            "temporary failure in name resolution",
+            "failed to lookup address",
+            "dns error",
+        ],
+    ),
+    (
+        "TLS error",
+        &[
+            "x509",
+            "certificate",
+            "tls:",
+            "tls handshake",
+            "invalidcertificate",
+        ],
+    ),
+    (
+        "credentials expired or rejected",
+        &["unauthorized", "expired", "you must be logged in"],
+    ),
+    ("forbidden by RBAC", &["forbidden"]),
+    ("timed out", &["timeout", "timed out", "deadline exceeded"]),
+];
+
+fn classify(error: &str) -> &'static str {
+    let error = error.to_lowercase();
+    FAILURE_CLASSES
+        .iter()
+        .find(|(_, patterns)| patterns.iter().any(|p| error.contains(p)))
+        .map(|(class, _)| *class)
+        .unwrap_or_else(|| {
+            if error.contains("connection refused") {
+                "connection refused"
+            } else {
+                "unreachable"
+            }
+        })
+}
+
+/// Probes the selected contexts in parallel and fails when a context not
+/// listed in `optional` is unhealthy.
+fn check_contexts(
+    context: Option<&str>,
+    all: bool,
+    timeout: u64,
+    optional: &[String],
+    aliases: &BTreeMap<String, String>,
+) -> Result<(), String> {
+    if tools::resolve("kubectl").is_none() && !cluster::native_available() {
+        return Err("Cannot check contexts: kubectl is not installed".to_string());
+    }
+
+    let kubeconfig = Kubeconfig::load()?;
+    let names: Vec<String> = if all {
+        kubeconfig.contexts.iter().map(|c| c.name.clone()).collect()
+    } else {
+        match context {
+            Some(query) => vec![resolve(&kubeconfig, aliases, query)?],
+            None => vec![kubeconfig
+                .current_context
+                .clone()
+                .ok_or_else(|| format!("No current context set in {}", describe_paths()))?],
         }
-        _ => {
-            println!("{}", "❌ Connection failed".red());
+    };
+    if names.is_empty() {
+        return Err(format!("No contexts found in {}", describe_paths()));
+    }
+    let optional = optional
+        .iter()
+        .map(|query| resolve(&kubeconfig, aliases, query))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    say!(
+        "{}",
+        format!(
+            "🩺 Checking {} context(s) (timeout {}s)...",
+            names.len(),
+            timeout
+        )
+        .cyan()
+        .bold()
+    );
+
+    let timeout = Duration::from_secs(timeout.max(1));
+    let results: Vec<_> = thread::scope(|scope| {
+        let handles: Vec<_> = names
+            .iter()
+            .map(|name| {
+                let namespace = kubeconfig
+                    .context(name)
+                    .and_then(|c| c.namespace.clone())
+                    .unwrap_or_else(|| "default".to_string());
+                scope.spawn(move || cluster::probe(name, &namespace, timeout))
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| {
+                h.join()
+                    .unwrap_or_else(|_| Err("probe panicked".to_string()))
+            })
+            .collect()
+    });
+
+    let width = names.iter().map(String::len).max().unwrap_or(0);
+    let mut unhealthy = Vec::new();
+    for (name, result) in names.iter().zip(results) {
+        match result {
+            Ok(probe) => say!(
+                "  ✅ {:<width$}  {:<14} {}ms",
+                name,
+                probe.version,
+                probe.latency.as_millis(),
+                width = width
+            ),
+            Err(e) => {
+                let is_optional = optional.contains(name);
+                let icon = if is_optional { "⚠️ " } else { "❌" };
+                let detail = e.lines().last().unwrap_or_default().trim();
+                say!(
+                    "  {} {:<width$}  {}: {}",
+                    icon,
+                    name,
+                    classify(&e).red(),
+                    detail,
+                    width = width
+                );
+                if !is_optional {
+                    unhealthy.push(name.as_str());
+                }
+            }
         }
     }
 
+    if !unhealthy.is_empty() {
+        return Err(format!(
+            "{} context(s) unhealthy: {}",
+            unhealthy.len(),
+            unhealthy.join(", ")
+        ));
+    }
     Ok(())
 }
+
+/// Prints shell exports for an isolated kubeconfig. Only the exports go to
+/// stdout so the output can be passed to `eval`.
+fn print_env(context: &str) -> Result<(), String> {
+    let path = kubeconfig::write_isolated(context)?;
+    let quoted = path.to_string_lossy().replace('\'', "'\\''");
+    println!("export KUBECONFIG='{}'", quoted);
+    esay!(
+        "✅ Kubeconfig for {} written to {}",
+        context.cyan(),
+        path.display()
+    );
+    eprintln!("   It is not removed automatically; run 'rm \"$KUBECONFIG\"' when done");
+    Ok(())
+}
+
+/// Runs `$SHELL` with `KUBECONFIG` pointing at an isolated kubeconfig,
+/// removing the file once the shell exits.
+fn run_shell(context: &str) -> Result<(), String> {
+    let path = kubeconfig::write_isolated(context)?;
+    let shell = env::var("SHELL")
+        .ok()
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "/bin/sh".to_string());
+
+    say!(
+        "🐚 Starting {} bound to context {} (exit to leave)",
+        shell,
+        context.cyan().bold()
+    );
+    let status = std::process::Command::new(&shell)
+        .env("KUBECONFIG", &path)
+        .status();
+    let _ = fs::remove_file(&path);
+
+    status.map_err(|e| format!("Failed to start {}: {}", shell, e))?;
+    say!("👋 Left the {} shell", context.cyan());
+    Ok(())
+}
+
+fn context_names(kubeconfig: &Kubeconfig) -> String {
+    let names: Vec<&str> = kubeconfig
+        .contexts
+        .iter()
+        .map(|c| c.name.as_str())
+        .collect();
+    if names.is_empty() {
+        "none".to_string()
+    } else {
+        names.join(", ")
+    }
+}
+
+fn describe_paths() -> String {
+    let paths: Vec<String> = kubeconfig::paths()
+        .iter()
+        .map(|p| p.display().to_string())
+        .collect();
+    paths.join(", ")
+}
@@ -1,23 +1,33 @@
+use crate::ci::{self, say};
+use crate::plugins;
+use crate::redact::redact;
+use crate::secrets::ensure_decryptable;
+use crate::tools;
 use crate::utils::{
-    check_command_available, check_helm_diff_plugin, confirm_production_deployment,
-    set_kubectl_context, update_helm_repos,
+    check_command_available, confirm_production_deployment, print_command, set_kubectl_context,
+    update_helm_repos, EXIT_CODE_CHANGES,
 };
 use colored::*;
+use std::io::{BufRead, BufReader};
 use std::path::Path;
-use std::process::Command;
+use std::process::{Command, ExitStatus, Stdio};
 
+/// Runs `helmfile sync`, or `helmfile diff` when `diff` is set. With
+/// `detailed_exitcode`, returns whether the diff found pending changes.
 #[allow(clippy::too_many_arguments)]
 pub fn execute(
     file: &str,
     environment: Option<&str>,
     dry_run: bool,
     diff: bool,
+    detailed_exitcode: bool,
     skip_deps: bool,
     concurrency: u8,
     context: Option<&str>,
+    install_missing: bool,
     verbose: bool,
-) -> Result<(), String> {
-    println!(
+) -> Result<bool, String> {
+    say!(
         "{}",
         format!("🚀 Deploying Helmfile: {}", file).cyan().bold()
     );
@@ -33,36 +43,46 @@ pub fn execute(
         set_kubectl_context(ctx, verbose)?;
     }
 
-    println!("📦 Updating Helm repositories...");
+    say!("📦 Updating Helm repositories...");
     update_helm_repos(verbose)?;
 
-    let mut cmd = Command::new("helmfile");
+    // Fail before touching any release if encrypted values cannot be read
+    ensure_decryptable(file, environment, verbose)?;
+
+    let mut cmd = tools::command("helmfile");
+    if ci::enabled() {
+        cmd.arg("--no-color");
+    }
     cmd.arg("-f").arg(file);
 
     if let Some(env) = environment {
         cmd.arg("-e").arg(env);
-        println!("📋 Environment: {}", env.yellow());
+        say!("📋 Environment: {}", env.yellow());
     }
 
     if diff {
-        check_helm_diff_plugin()?;
-        cmd.arg("diff");
-        println!("🔍 Running diff to show changes");
+        let diff_plugin = plugins::required("diff").expect("diff is a required plugin");
+        plugins::ensure(diff_plugin, install_missing, verbose)?;
+        cmd.args(["diff", "--suppress-secrets"]);
+        if detailed_exitcode {
+            cmd.arg("--detailed-exitcode");
+        }
+        say!("🔍 Running diff to show changes");
     } else if dry_run {
         cmd.arg("sync");
         cmd.args(["--args", "--dry-run"]);
-        println!("🔍 Running in dry-run mode");
+        say!("🔍 Running in dry-run mode");
     } else {
         cmd.arg("sync");
     }
 
     if skip_deps {
         cmd.arg("--skip-deps");
-        println!("⏭️  Skipping dependency update");
+        say!("⏭️  Skipping dependency update");
     }
 
     cmd.arg("--concurrency").arg(concurrency.to_string());
-    println!("🔄 Concurrency level: {}", concurrency);
+    say!("🔄 Concurrency level: {}", concurrency);
 
     // Safety check for production deployments
     if !dry_run && !diff {
@@ -70,17 +90,99 @@ pub fn execute(
     }
 
     if verbose {
-        println!("🔧 Command: {:?}", cmd);
+        print_command(&cmd);
     }
 
-    println!("🚀 Running helmfile operation...");
-    let status = cmd
-        .status()
-        .map_err(|e| format!("Failed to execute helmfile: {}", e))?;
+    say!("🚀 Running helmfile operation...");
+    let (status, output) = if ci::summary_enabled() {
+        run_teed(&mut cmd)?
+    } else {
+        let status = cmd
+            .status()
+            .map_err(|e| format!("Failed to execute helmfile: {}", e))?;
+        (status, String::new())
+    };
+
+    // With --detailed-exitcode, helmfile diff exits 2 when changes exist
+    let changes = detailed_exitcode && status.code() == Some(EXIT_CODE_CHANGES);
+    let mode = if diff {
+        "diff"
+    } else if dry_run {
+        "dry run"
+    } else {
+        "sync"
+    };
+    let outcome = if changes {
+        "△ changes detected"
+    } else if !status.success() {
+        "❌ failed"
+    } else if detailed_exitcode {
+        "✅ no changes"
+    } else {
+        "✅ succeeded"
+    };
+    ci::summary(&summary(file, environment, mode, outcome, &output));
+
+    if changes {
+        return Ok(true);
+    }
 
     if !status.success() {
         return Err("Helmfile operation failed".to_string());
     }
 
-    Ok(())
+    Ok(false)
+}
+
+/// Runs `cmd`, echoing its stdout while also capturing it.
+fn run_teed(cmd: &mut Command) -> Result<(ExitStatus, String), String> {
+    let mut child = cmd
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to execute helmfile: {}", e))?;
+
+    // Echoed line by line as it streams, which masks URL credentials and
+    // sensitive `key=value` pairs but not multi-line Secret data; the
+    // capture is redacted as a whole when the summary is written
+    let mut captured = String::new();
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            println!("{}", redact(&line));
+            captured.push_str(&line);
+            captured.push('\n');
+        }
+    }
+
+    let status = child
+        .wait()
+        .map_err(|e| format!("Failed to execute helmfile: {}", e))?;
+    Ok((status, captured))
+}
+
+/// Markdown job summary of a deploy, with the helmfile output folded away.
+fn summary(
+    file: &str,
+    environment: Option<&str>,
+    mode: &str,
+    outcome: &str,
+    output: &str,
+) -> String {
+    let mut markdown = format!(
+        "## helmctl deploy\n\n\
+         | Helmfile | Environment | Mode | Result |\n\
+         |---|---|---|---|\n\
+         | `{}` | `{}` | {} | {} |\n",
+        file,
+        environment.unwrap_or("default"),
+        mode,
+        outcome
+    );
+    if !output.trim().is_empty() {
+        let lang = if mode == "diff" { "diff" } else { "text" };
+        markdown.push_str(&format!(
+            "\n<details><summary>helmfile output</summary>\n\n{}\n\n</details>\n",
+            ci::code_block(lang, output)
+        ));
+    }
+    markdown
 }
@@ -0,0 +1,136 @@
+use crate::ci::say;
+use crate::cli::NoiseFilter;
+use crate::commands::{compare, validate};
+use crate::tools;
+use crate::utils::{check_command_available, print_command, scratch_dir};
+use colored::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// A detached `git worktree`, removed again when dropped.
+struct Worktree {
+    dir: PathBuf,
+    path: PathBuf,
+}
+
+impl Worktree {
+    fn add(revision: &str, verbose: bool) -> Result<Self, String> {
+        let safe: String = revision
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        let dir = scratch_dir(&format!("rev-{}", safe))?;
+        let path = dir.join("tree");
+
+        let mut cmd = git();
+        cmd.args(["worktree", "add", "--detach", "--quiet"])
+            .arg(&path)
+            .arg(revision);
+        if let Err(e) = run(cmd, verbose) {
+            let _ = fs::remove_dir_all(&dir);
+            return Err(format!("Failed to check out {}: {}", revision, e));
+        }
+        Ok(Worktree { dir, path })
+    }
+}
+
+impl Drop for Worktree {
+    fn drop(&mut self) {
+        let _ = git()
+            .args(["worktree", "remove", "--force"])
+            .arg(&self.path)
+            .output();
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Renders `file` at `base` and at `head` (the working tree when `None`)
+/// and shows how the manifests differ. Returns whether they do.
+pub fn execute(
+    file: &str,
+    base: &str,
+    head: Option<&str>,
+    environment: Option<&str>,
+    ignore: &[NoiseFilter],
+    verbose: bool,
+) -> Result<bool, String> {
+    check_command_available("git")?;
+
+    let head_label = head.unwrap_or("working tree");
+    say!(
+        "{}",
+        format!("🔍 Comparing {} between {} and {}", file, base, head_label)
+            .cyan()
+            .bold()
+    );
+    if let Some(env) = environment {
+        say!("📋 Environment: {}", env.yellow());
+    }
+
+    if Path::new(file).is_absolute() {
+        return Err("diff-rev needs a helmfile path relative to the current directory".to_string());
+    }
+
+    // The helmfile's location inside the repository, to find it in worktrees
+    let mut cmd = git();
+    cmd.args(["rev-parse", "--show-prefix"]);
+    let prefix = run(cmd, verbose).map_err(|e| format!("Not in a git repository: {}", e))?;
+    let relative = Path::new(prefix.trim()).join(file);
+
+    for revision in std::iter::once(base).chain(head) {
+        let mut cmd = git();
+        cmd.args(["rev-parse", "--verify", "--quiet"])
+            .arg(format!("{}^{{commit}}", revision));
+        run(cmd, verbose).map_err(|_| format!("Unknown git revision: {}", revision))?;
+    }
+
+    let old = render_at(Some(base), &relative, file, environment, verbose)?;
+    let new = render_at(head, &relative, file, environment, verbose)?;
+
+    Ok(compare::report(&old, &new, (base, head_label), ignore))
+}
+
+fn render_at(
+    revision: Option<&str>,
+    relative: &Path,
+    file: &str,
+    environment: Option<&str>,
+    verbose: bool,
+) -> Result<compare::Releases, String> {
+    let Some(revision) = revision else {
+        say!("📝 Rendering working tree");
+        return validate::render_releases(file, environment, verbose);
+    };
+
+    say!("📝 Rendering {}", revision.yellow());
+    let worktree = Worktree::add(revision, verbose)?;
+    let path = worktree.path.join(relative);
+    if !path.exists() {
+        // A helmfile added on the head side renders as all-new releases
+        say!(
+            "⚠️  {} does not exist at {}",
+            relative.display(),
+            revision.yellow()
+        );
+        return Ok(compare::Releases::new());
+    }
+    validate::render_releases(&path.to_string_lossy(), environment, verbose)
+}
+
+fn git() -> Command {
+    tools::command("git")
+}
+
+fn run(mut cmd: Command, verbose: bool) -> Result<String, String> {
+    if verbose {
+        print_command(&cmd);
+    }
+    let output = cmd
+        .output()
+        .map_err(|e| format!("Failed to execute git: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
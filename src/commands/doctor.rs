@@ -0,0 +1,386 @@
+use crate::ci::{self, say};
+use crate::cli::OutputFormat;
+use crate::cluster;
+use crate::config::{Config, Repository};
+use crate::kubeconfig::{self, Kubeconfig};
+use crate::plugins::{self, REQUIRED_PLUGINS};
+use crate::redact::redact;
+use crate::tools::{self, Tool, TOOLS};
+use crate::version::Version;
+use colored::*;
+use serde::Serialize;
+use std::fs;
+use std::process::Stdio;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Serialize)]
+struct Check {
+    category: &'static str,
+    name: String,
+    status: Status,
+    detail: String,
+}
+
+impl Check {
+    fn new(category: &'static str, name: &str, status: Status, detail: String) -> Check {
+        Check {
+            category,
+            name: name.to_string(),
+            status,
+            detail,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Report {
+    checks: Vec<Check>,
+    passed: usize,
+    warnings: usize,
+    failures: usize,
+}
+
+pub fn execute(explicit_config: Option<&str>, output: OutputFormat) -> Result<(), String> {
+    let mut checks = Vec::new();
+
+    let config = check_config(explicit_config, &mut checks);
+    check_tools(&mut checks);
+    check_plugins(&mut checks);
+    check_kubeconfig(&mut checks);
+    check_cluster(&mut checks);
+    check_repositories(
+        config.and_then(|c| c.repositories).unwrap_or_default(),
+        &mut checks,
+    );
+
+    let count = |status| checks.iter().filter(|c| c.status == status).count();
+    let report = Report {
+        passed: count(Status::Pass),
+        warnings: count(Status::Warn),
+        failures: count(Status::Fail),
+        checks,
+    };
+
+    match output {
+        OutputFormat::Json => {
+            let json = serde_json::to_string_pretty(&report)
+                .map_err(|e| format!("Failed to serialize report: {}", e))?;
+            println!("{}", json);
+        }
+        OutputFormat::Text => print_report(&report),
+    }
+
+    if report.failures > 0 {
+        return Err(format!(
+            "{} check(s) failed, {} warning(s)",
+            report.failures, report.warnings
+        ));
+    }
+
+    Ok(())
+}
+
+fn print_report(report: &Report) {
+    say!("{}", "🩺 Checking helmctl environment".cyan().bold());
+
+    let mut category = "";
+    for check in &report.checks {
+        if check.category != category {
+            category = check.category;
+            println!();
+            println!("{}", category.bright_white().bold());
+        }
+
+        // CI mode drops emoji, so spell the status out there
+        let symbol = match (check.status, ci::enabled()) {
+            (Status::Pass, false) => "✅",
+            (Status::Warn, false) => "⚠️ ",
+            (Status::Fail, false) => "❌",
+            (Status::Pass, true) => "ok  ",
+            (Status::Warn, true) => "warn",
+            (Status::Fail, true) => "fail",
+        };
+        let detail = match check.status {
+            Status::Pass => check.detail.normal(),
+            Status::Warn => check.detail.yellow(),
+            Status::Fail => check.detail.red(),
+        };
+        println!("  {} {:<22} {}", symbol, check.name, detail);
+    }
+
+    println!();
+    println!(
+        "{} passed, {} warning(s), {} failed",
+        report.passed.to_string().green(),
+        report.warnings.to_string().yellow(),
+        report.failures.to_string().red()
+    );
+}
+
+fn check_config(explicit: Option<&str>, checks: &mut Vec<Check>) -> Option<Config> {
+    for layer in Config::layers(explicit) {
+        if !layer.path.exists() {
+            continue;
+        }
+
+        let name = format!("{} config", layer.name);
+        let check = match Config::validate_file(&layer.path.to_string_lossy()) {
+            Ok(issues) if issues.is_empty() => Check::new(
+                "Configuration",
+                &name,
+                Status::Pass,
+                layer.path.display().to_string(),
+            ),
+            Ok(issues) => Check::new(
+                "Configuration",
+                &name,
+                Status::Fail,
+                format!(
+                    "{}: {} issue(s), first: {}",
+                    layer.path.display(),
+                    issues.len(),
+                    issues[0]
+                ),
+            ),
+            Err(e) => Check::new("Configuration", &name, Status::Fail, e),
+        };
+        checks.push(check);
+    }
+
+    match Config::resolve(explicit) {
+        Ok(resolved) => Some(resolved.config),
+        Err(e) => {
+            // File issues are already reported above
+            if !checks.iter().any(|c| c.status == Status::Fail) {
+                checks.push(Check::new("Configuration", "environment", Status::Fail, e));
+            }
+            None
+        }
+    }
+}
+
+fn check_tools(checks: &mut Vec<Check>) {
+    for tool in &TOOLS {
+        checks.push(check_tool(tool));
+    }
+}
+
+fn check_tool(tool: &Tool) -> Check {
+    let missing = if tool.required {
+        Status::Fail
+    } else {
+        Status::Warn
+    };
+
+    let Some(path) = tools::resolve(tool.name) else {
+        let detail = match tools::override_for(tool.name) {
+            Some(configured) => format!("configured path {} is not executable", configured),
+            None => format!("not found in PATH (needed for {})", tool.purpose),
+        };
+        return Check::new("Tools", tool.name, missing, detail);
+    };
+
+    let version = match tool.detect_version() {
+        Ok(Some(version)) => version,
+        Ok(None) => {
+            return Check::new(
+                "Tools",
+                tool.name,
+                Status::Warn,
+                format!("{}: version could not be determined", path.display()),
+            )
+        }
+        Err(e) => return Check::new("Tools", tool.name, missing, e),
+    };
+
+    let minimum = tool.minimum_version();
+    if version < minimum {
+        return Check::new(
+            "Tools",
+            tool.name,
+            missing,
+            format!("{} is older than the minimum {}", version, minimum),
+        );
+    }
+
+    if let Some(limit) = tool.tested_below.and_then(Version::parse) {
+        if version >= limit {
+            return Check::new(
+                "Tools",
+                tool.name,
+                Status::Warn,
+                format!("{} is newer than tested (< {})", version, limit),
+            );
+        }
+    }
+
+    Check::new(
+        "Tools",
+        tool.name,
+        Status::Pass,
+        format!("{} ({})", version, path.display()),
+    )
+}
+
+fn check_plugins(checks: &mut Vec<Check>) {
+    let installed = match plugins::installed() {
+        Ok(installed) => installed,
+        Err(_) => {
+            checks.push(Check::new(
+                "Tools",
+                "helm plugins",
+                Status::Warn,
+                "skipped, helm is not available".to_string(),
+            ));
+            return;
+        }
+    };
+
+    for required in &REQUIRED_PLUGINS {
+        let name = format!("helm-{} plugin", required.name);
+        let check = match required.check(&installed) {
+            Ok(version) => Check::new("Tools", &name, Status::Pass, version.to_string()),
+            Err(e) => Check::new("Tools", &name, Status::Warn, e),
+        };
+        checks.push(check);
+    }
+}
+
+fn check_kubeconfig(checks: &mut Vec<Check>) {
+    let paths = kubeconfig::paths();
+    if paths.is_empty() {
+        checks.push(Check::new(
+            "Cluster",
+            "kubeconfig",
+            Status::Fail,
+            "neither KUBECONFIG nor HOME is set".to_string(),
+        ));
+        return;
+    }
+
+    for path in paths {
+        let name = "kubeconfig";
+        let check = match fs::read_to_string(&path) {
+            Err(e) => Check::new(
+                "Cluster",
+                name,
+                Status::Fail,
+                format!("{}: {}", path.display(), e),
+            ),
+            Ok(content) => match serde_yaml::from_str::<serde_yaml::Value>(&content) {
+                Ok(_) => Check::new("Cluster", name, Status::Pass, path.display().to_string()),
+                Err(e) => Check::new(
+                    "Cluster",
+                    name,
+                    Status::Fail,
+                    format!("{}: invalid YAML: {}", path.display(), e),
+                ),
+            },
+        };
+        checks.push(check);
+    }
+}
+
+fn check_cluster(checks: &mut Vec<Check>) {
+    if tools::resolve("kubectl").is_none() && !cluster::native_available() {
+        checks.push(Check::new(
+            "Cluster",
+            "current context",
+            Status::Warn,
+            "skipped, kubectl is not available".to_string(),
+        ));
+        return;
+    }
+
+    let context = match Kubeconfig::load().map(|k| k.current_context) {
+        Ok(Some(context)) => context,
+        Ok(None) | Err(_) => {
+            checks.push(Check::new(
+                "Cluster",
+                "current context",
+                Status::Fail,
+                "no current context set".to_string(),
+            ));
+            return;
+        }
+    };
+
+    let check = match cluster::server_version() {
+        Ok(version) => Check::new(
+            "Cluster",
+            "current context",
+            Status::Pass,
+            format!("{} reachable (server {})", context, version),
+        ),
+        Err(reason) => Check::new(
+            "Cluster",
+            "current context",
+            Status::Fail,
+            format!("{}: {}", context, reason),
+        ),
+    };
+    checks.push(check);
+}
+
+#[derive(serde::Deserialize)]
+struct HelmRepo {
+    name: String,
+    url: String,
+}
+
+fn check_repositories(configured: Vec<Repository>, checks: &mut Vec<Check>) {
+    if configured.is_empty() {
+        return;
+    }
+
+    let output = tools::command("helm")
+        .args(["repo", "list", "-o", "json"])
+        .stderr(Stdio::null())
+        .output();
+
+    // `helm repo list` fails when no repositories have been added yet
+    let added: Vec<HelmRepo> = match output {
+        Ok(output) => serde_json::from_slice(&output.stdout).unwrap_or_default(),
+        Err(_) => {
+            checks.push(Check::new(
+                "Repositories",
+                "repositories",
+                Status::Warn,
+                "skipped, helm is not available".to_string(),
+            ));
+            return;
+        }
+    };
+
+    for repo in configured {
+        let check = match added.iter().find(|r| r.name == repo.name) {
+            Some(found) if found.url.trim_end_matches('/') == repo.url.trim_end_matches('/') => {
+                Check::new("Repositories", &repo.name, Status::Pass, redact(&repo.url))
+            }
+            Some(found) => Check::new(
+                "Repositories",
+                &repo.name,
+                Status::Warn,
+                format!("added with a different URL: {}", redact(&found.url)),
+            ),
+            None => Check::new(
+                "Repositories",
+                &repo.name,
+                Status::Warn,
+                format!(
+                    "not added (helm repo add {} {})",
+                    repo.name,
+                    redact(&repo.url)
+                ),
+            ),
+        };
+        checks.push(check);
+    }
+}
@@ -1,18 +1,52 @@
-use crate::utils::{check_command_available, confirm_production_deployment, set_kubectl_context};
+use crate::ci::say;
+use crate::cli::DryRunMode;
+use crate::cluster::{self, Resource};
+use crate::kubeconfig;
+use crate::manifest::{self, Object};
+use crate::redact::redact;
+use crate::tools;
+use crate::utils::{
+    check_command_available, confirm_production_deployment, print_command, print_diff_line,
+    set_kubectl_context,
+};
 use colored::*;
 use std::path::Path;
 use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Field manager recorded by server-side apply when none is given.
+const DEFAULT_FIELD_MANAGER: &str = "helmctl";
+
+/// File names kubectl recognises as a kustomization root.
+const KUSTOMIZATION_FILES: [&str; 3] = ["kustomization.yaml", "kustomization.yml", "Kustomization"];
+
+/// Workload kinds whose rollout `--wait` tracks after applying.
+const ROLLOUT_KINDS: [&str; 4] = ["Deployment", "StatefulSet", "DaemonSet", "Job"];
+
+/// How often `--wait` checks on a Job.
+const JOB_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Flags controlling how manifests are applied.
+pub struct ApplyOptions {
+    pub dry_run: Option<DryRunMode>,
+    pub server_side: bool,
+    pub field_manager: Option<String>,
+    pub force_conflicts: bool,
+    pub prune: bool,
+    pub selector: Option<String>,
+}
 
 pub fn execute(
     manifest: &str,
     namespace: Option<&str>,
     context: Option<&str>,
-    dry_run: bool,
+    options: &ApplyOptions,
     wait: bool,
     timeout: u32,
     verbose: bool,
 ) -> Result<(), String> {
-    println!(
+    say!(
         "{}",
         format!("☸️  Deploying to Kubernetes: {}", manifest)
             .cyan()
@@ -23,42 +57,48 @@ pub fn execute(
         return Err(format!("Manifest not found: {}", manifest));
     }
 
+    if tools::resolve("kubectl").is_none() && cluster::native_available() {
+        return apply_native(manifest, namespace, context, options, wait);
+    }
+
     check_command_available("kubectl")?;
 
     if let Some(ctx) = context {
         set_kubectl_context(ctx, verbose)?;
     }
 
-    let mut cmd = Command::new("kubectl");
+    let mut cmd = tools::command("kubectl");
 
     if let Some(ns) = namespace {
         cmd.arg("--namespace").arg(ns);
-        println!("📦 Namespace: {}", ns.yellow());
+        say!("📦 Namespace: {}", ns.yellow());
     }
 
-    cmd.arg("apply").arg("-f").arg(manifest);
+    cmd.arg("apply");
+    add_apply_args(&mut cmd, manifest, options);
 
-    if dry_run {
-        cmd.arg("--dry-run=client");
-        println!("🔍 Running in dry-run mode");
-    }
-
-    if wait {
-        cmd.arg("--wait");
-        cmd.arg("--timeout").arg(format!("{}s", timeout));
-        println!("⏳ Waiting for deployment (timeout: {}s)", timeout);
+    match options.dry_run {
+        Some(DryRunMode::Client) => {
+            cmd.arg("--dry-run=client");
+            say!("🔍 Running in dry-run mode");
+        }
+        Some(DryRunMode::Server) => {
+            cmd.arg("--dry-run=server");
+            say!("🔍 Running in server-side dry-run mode");
+        }
+        None => {}
     }
 
     // Safety check for production deployments
-    if !dry_run {
+    if options.dry_run.is_none() {
         confirm_production_deployment(None, context)?;
     }
 
     if verbose {
-        println!("🔧 Command: {:?}", cmd);
+        print_command(&cmd);
     }
 
-    println!("☸️  Running kubectl apply...");
+    say!("☸️  Running kubectl apply...");
     let status = cmd
         .status()
         .map_err(|e| format!("Failed to execute kubectl: {}", e))?;
@@ -67,5 +107,412 @@ pub fn execute(
         return Err("Kubernetes deployment failed".to_string());
     }
 
+    if wait && options.dry_run.is_none() {
+        let objects = applied_objects(manifest, verbose)?;
+        // Objects without a namespace went to the context's default one
+        let namespace = namespace
+            .map(str::to_string)
+            .or_else(|| kubeconfig::context_namespace(context));
+        wait_for_rollouts(&objects, namespace.as_deref(), timeout, verbose)?;
+    }
+
+    Ok(())
+}
+
+/// Applies plain manifests through the API server when kubectl is not
+/// installed. The native client always applies server-side; features that
+/// rely on kubectl itself are rejected rather than silently skipped.
+fn apply_native(
+    manifest: &str,
+    namespace: Option<&str>,
+    context: Option<&str>,
+    options: &ApplyOptions,
+    wait: bool,
+) -> Result<(), String> {
+    let unsupported = [
+        (is_kustomization(manifest), "kustomizations"),
+        (options.prune, "--prune"),
+        (options.selector.is_some(), "--selector"),
+        (wait, "--wait"),
+    ];
+    if let Some((_, feature)) = unsupported.iter().find(|(used, _)| *used) {
+        return Err(format!(
+            "{} requires kubectl, which is not installed",
+            feature
+        ));
+    }
+
+    if let Some(ctx) = context {
+        cluster::select_context(ctx);
+        say!("🔄 Using context: {}", ctx.yellow());
+    }
+    if let Some(ns) = namespace {
+        say!("📦 Namespace: {}", ns.yellow());
+    }
+
+    let documents = manifest::load_documents(Path::new(manifest))?;
+    if documents.is_empty() {
+        return Err(format!("No Kubernetes objects found in {}", manifest));
+    }
+
+    if options.dry_run == Some(DryRunMode::Client) {
+        say!("🔍 Running in dry-run mode");
+        for document in &documents {
+            println!("  {} (dry run)", Object::from(document).reference());
+        }
+        return Ok(());
+    }
+
+    let dry_run = options.dry_run == Some(DryRunMode::Server);
+    if dry_run {
+        say!("🔍 Running in server-side dry-run mode");
+    } else {
+        confirm_production_deployment(None, context)?;
+    }
+
+    let manager = options
+        .field_manager
+        .as_deref()
+        .unwrap_or(DEFAULT_FIELD_MANAGER);
+    say!(
+        "☸️  Applying {} object(s) through the Kubernetes API (field manager: {})...",
+        documents.len(),
+        manager.yellow()
+    );
+
+    let applied = cluster::apply(
+        &documents,
+        namespace,
+        manager,
+        options.force_conflicts,
+        dry_run,
+    )?;
+    for reference in applied {
+        println!("  {} serverside-applied", reference);
+    }
+
+    Ok(())
+}
+
+/// Runs `kubectl diff` for the manifest against the live cluster and prints
+/// a colored unified diff per object. Returns whether any object differs.
+pub fn diff(
+    manifest: &str,
+    namespace: Option<&str>,
+    context: Option<&str>,
+    options: &ApplyOptions,
+    verbose: bool,
+) -> Result<bool, String> {
+    say!(
+        "{}",
+        format!("🔍 Diffing manifests against cluster: {}", manifest)
+            .cyan()
+            .bold()
+    );
+
+    if !Path::new(manifest).exists() {
+        return Err(format!("Manifest not found: {}", manifest));
+    }
+
+    check_command_available("kubectl")?;
+
+    if let Some(ctx) = context {
+        set_kubectl_context(ctx, verbose)?;
+    }
+
+    let mut cmd = tools::command("kubectl");
+
+    if let Some(ns) = namespace {
+        cmd.arg("--namespace").arg(ns);
+        say!("📦 Namespace: {}", ns.yellow());
+    }
+
+    cmd.arg("diff");
+    add_apply_args(&mut cmd, manifest, options);
+
+    if verbose {
+        print_command(&cmd);
+    }
+
+    let output = cmd
+        .output()
+        .map_err(|e| format!("Failed to execute kubectl diff: {}", e))?;
+
+    // kubectl diff exits 0 without changes, 1 with changes, >1 on error
+    match output.status.code() {
+        Some(0) => {
+            say!("{}", "✅ No changes".green());
+            Ok(false)
+        }
+        Some(1) => {
+            print_diff(&String::from_utf8_lossy(&output.stdout));
+            Ok(true)
+        }
+        _ => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            Err(format!("Kubernetes diff failed:\n{}", stderr))
+        }
+    }
+}
+
+/// Adds the manifest source and the apply flags shared by `apply` and `diff`.
+fn add_apply_args(cmd: &mut Command, manifest: &str, options: &ApplyOptions) {
+    if is_kustomization(manifest) {
+        cmd.arg("-k").arg(manifest);
+        say!("🧩 Kustomization detected");
+    } else {
+        cmd.arg("-f").arg(manifest);
+        if Path::new(manifest).is_dir() {
+            cmd.arg("--recursive");
+        }
+    }
+
+    if options.server_side {
+        let manager = options
+            .field_manager
+            .as_deref()
+            .unwrap_or(DEFAULT_FIELD_MANAGER);
+        cmd.arg("--server-side");
+        cmd.arg(format!("--field-manager={}", manager));
+        say!(
+            "🖥️  Server-side apply (field manager: {})",
+            manager.yellow()
+        );
+
+        if options.force_conflicts {
+            cmd.arg("--force-conflicts");
+            say!("⚠️  Forcing ownership of conflicting fields");
+        }
+    }
+
+    if let Some(selector) = &options.selector {
+        cmd.arg("--selector").arg(selector);
+        say!("🏷️  Selector: {}", selector.yellow());
+    }
+
+    if options.prune {
+        cmd.arg("--prune");
+        say!("🗑️  Pruning resources not present in the manifests");
+    }
+}
+
+/// Prints `kubectl diff` output with a header per object. kubectl names the
+/// compared files `<group>.<version>.<Kind>.<namespace>.<name>`.
+fn print_diff(output: &str) {
+    for line in redact(output).lines() {
+        if line.starts_with("diff ") {
+            let object = line.rsplit('/').next().unwrap_or(line);
+            say!("\n{}", format!("📄 {}", object).bright_white().bold());
+        } else {
+            print_diff_line(line);
+        }
+    }
+}
+
+/// Objects contained in the applied manifest, rendering kustomizations
+/// through `kubectl kustomize`.
+fn applied_objects(manifest: &str, verbose: bool) -> Result<Vec<Object>, String> {
+    if !is_kustomization(manifest) {
+        return manifest::load(Path::new(manifest));
+    }
+
+    let mut cmd = tools::command("kubectl");
+    cmd.args(["kustomize", manifest]);
+
+    if verbose {
+        print_command(&cmd);
+    }
+
+    let output = cmd
+        .output()
+        .map_err(|e| format!("Failed to execute kubectl kustomize: {}", e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("Failed to render kustomization:\n{}", stderr));
+    }
+
+    manifest::parse(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Waits for every workload in `objects` to finish rolling out, sharing a
+/// single deadline across all of them.
+fn wait_for_rollouts(
+    objects: &[Object],
+    namespace: Option<&str>,
+    timeout: u32,
+    verbose: bool,
+) -> Result<(), String> {
+    let workloads: Vec<&Object> = objects
+        .iter()
+        .filter(|o| ROLLOUT_KINDS.contains(&o.kind.as_str()))
+        .collect();
+
+    if workloads.is_empty() {
+        say!("⏭️  No workloads to wait for");
+        return Ok(());
+    }
+
+    say!(
+        "⏳ Waiting for {} workload(s) to roll out (timeout: {}s)",
+        workloads.len(),
+        timeout
+    );
+
+    let deadline = Instant::now() + Duration::from_secs(timeout as u64);
+
+    for (i, object) in workloads.iter().enumerate() {
+        let remaining = deadline.saturating_duration_since(Instant::now()).as_secs();
+        if remaining == 0 {
+            return Err(format!(
+                "Timed out after {}s waiting for {}",
+                timeout,
+                object.reference()
+            ));
+        }
+
+        let ns = object.namespace.as_deref().or(namespace);
+        say!(
+            "[{}/{}] ⏳ {}",
+            i + 1,
+            workloads.len(),
+            object.reference().yellow()
+        );
+
+        let result = if object.kind == "Job" {
+            wait_for_job(object, ns, Duration::from_secs(remaining), verbose)
+        } else {
+            let mut cmd = tools::command("kubectl");
+            if let Some(ns) = ns {
+                cmd.arg("--namespace").arg(ns);
+            }
+            cmd.args(["rollout", "status", &object.reference()]);
+            cmd.arg(format!("--timeout={}s", remaining));
+
+            if verbose {
+                print_command(&cmd);
+            }
+
+            let status = cmd
+                .status()
+                .map_err(|e| format!("Failed to execute kubectl: {}", e))?;
+            if status.success() {
+                Ok(())
+            } else {
+                Err("rollout did not finish".to_string())
+            }
+        };
+
+        if let Err(reason) = result {
+            let events = resource_events(object, ns);
+            return Err(format!(
+                "Rollout of {} did not complete ({}):\n{}",
+                object.reference(),
+                reason,
+                events
+            ));
+        }
+
+        say!("{}", format!("✅ {} is ready", object.reference()).green());
+    }
+
     Ok(())
 }
+
+/// Polls a Job until it completes. Unlike `kubectl wait
+/// --for=condition=complete`, a Job that has failed ends the wait at once.
+fn wait_for_job(
+    object: &Object,
+    namespace: Option<&str>,
+    timeout: Duration,
+    verbose: bool,
+) -> Result<(), String> {
+    let deadline = Instant::now() + timeout;
+
+    loop {
+        let mut cmd = tools::command("kubectl");
+        if let Some(ns) = namespace {
+            cmd.arg("--namespace").arg(ns);
+        }
+        cmd.args(["get", &object.reference(), "--output", "json"]);
+
+        if verbose {
+            print_command(&cmd);
+        }
+
+        let output = cmd
+            .output()
+            .map_err(|e| format!("Failed to execute kubectl: {}", e))?;
+        if output.status.success() {
+            let job: serde_json::Value = serde_json::from_slice(&output.stdout)
+                .map_err(|e| format!("Failed to parse kubectl output: {}", e))?;
+            let conditions = job["status"]["conditions"]
+                .as_array()
+                .cloned()
+                .unwrap_or_default();
+            let holds = |kind: &str| {
+                conditions
+                    .iter()
+                    .find(|c| c["type"] == kind && c["status"] == "True")
+            };
+
+            if holds("Complete").is_some() {
+                return Ok(());
+            }
+            if let Some(failed) = holds("Failed") {
+                return Err(format!(
+                    "job failed: {}",
+                    failed["message"]
+                        .as_str()
+                        .or(failed["reason"].as_str())
+                        .unwrap_or("unknown reason")
+                ));
+            }
+        }
+
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(format!("timed out after {}s", timeout.as_secs()));
+        }
+        thread::sleep(JOB_POLL_INTERVAL.min(remaining));
+    }
+}
+
+fn resource_events(object: &Object, namespace: Option<&str>) -> String {
+    let selector = format!(
+        "involvedObject.kind={},involvedObject.name={}",
+        object.kind, object.name
+    );
+
+    let mut events = match cluster::list(Resource::Events, namespace, None, Some(&selector)) {
+        Ok(events) => events,
+        Err(_) => return "Could not retrieve events".to_string(),
+    };
+    if events.is_empty() {
+        return "No events recorded".to_string();
+    }
+
+    events.sort_by(|a, b| {
+        a["lastTimestamp"]
+            .as_str()
+            .cmp(&b["lastTimestamp"].as_str())
+    });
+    events
+        .iter()
+        .map(|event| {
+            format!(
+                "{}  {:<8} {:<20} {}",
+                event["lastTimestamp"].as_str().unwrap_or("-"),
+                event["type"].as_str().unwrap_or(""),
+                event["reason"].as_str().unwrap_or(""),
+                event["message"].as_str().unwrap_or("").trim()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn is_kustomization(manifest: &str) -> bool {
+    let path = Path::new(manifest);
+    path.is_dir() && KUSTOMIZATION_FILES.iter().any(|f| path.join(f).is_file())
+}
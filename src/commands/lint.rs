@@ -1,7 +1,10 @@
-use crate::utils::{check_command_available, update_helm_repos};
+use crate::ci::{self, say, Level};
+use crate::redact::redact;
+use crate::tools;
+use crate::utils::{check_command_available, print_command, update_helm_repos};
 use colored::*;
 use std::path::Path;
-use std::process::{Command, Stdio};
+use std::process::Stdio;
 
 pub fn execute(
     file: &str,
@@ -10,7 +13,7 @@ pub fn execute(
     template_only: bool,
     verbose: bool,
 ) -> Result<(), String> {
-    println!("{}", format!("🔍 Linting Helmfile: {}", file).cyan().bold());
+    say!("{}", format!("🔍 Linting Helmfile: {}", file).cyan().bold());
 
     if !Path::new(file).exists() {
         return Err(format!("Helmfile not found: {}", file));
@@ -20,28 +23,28 @@ pub fn execute(
     check_command_available("helm")?;
 
     if !template_only {
-        println!("📦 Updating Helm repositories...");
+        say!("📦 Updating Helm repositories...");
         update_helm_repos(verbose)?;
     }
 
-    let mut cmd = Command::new("helmfile");
+    let mut cmd = tools::command("helmfile");
     cmd.arg("-f").arg(file);
 
     if let Some(env) = environment {
         cmd.arg("-e").arg(env);
-        println!("📋 Environment: {}", env.yellow());
+        say!("📋 Environment: {}", env.yellow());
     }
 
     if template_only {
         cmd.args(["template", "--skip-deps"]);
-        println!("🔍 Validating templates only...");
+        say!("🔍 Validating templates only...");
     } else {
         cmd.args(["lint", "--skip-deps"]);
-        println!("🔍 Running full helmfile lint...");
+        say!("🔍 Running full helmfile lint...");
     }
 
     if verbose {
-        println!("🔧 Command: {:?}", cmd);
+        print_command(&cmd);
     }
 
     let output = cmd
@@ -50,24 +53,33 @@ pub fn execute(
         .output()
         .map_err(|e| format!("Failed to execute helmfile: {}", e))?;
 
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    annotate_findings(file, &stdout, &stderr);
+
     if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !stderr.lines().any(is_finding) {
+            let first = stderr.lines().find(|l| !l.trim().is_empty());
+            ci::annotate(
+                Level::Error,
+                file,
+                None,
+                first.unwrap_or("helmfile lint failed"),
+            );
+        }
         return Err(format!("Helmfile lint failed:\n{}", stderr));
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let stderr = String::from_utf8_lossy(&output.stderr);
-
     let warning_count = stdout.matches("WARNING").count() + stderr.matches("WARNING").count();
     let error_count = stdout.matches("ERROR").count() + stderr.matches("ERROR").count();
 
     if error_count > 0 {
-        println!("{}", format!("❌ Found {} errors", error_count).red());
+        say!("{}", format!("❌ Found {} errors", error_count).red());
         return Err("Linting failed with errors".to_string());
     }
 
     if warning_count > 0 {
-        println!(
+        say!(
             "{}",
             format!("⚠️  Found {} warnings", warning_count).yellow()
         );
@@ -77,9 +89,31 @@ pub fn execute(
     }
 
     if verbose && !stdout.is_empty() {
-        println!("Output:\n{}", stdout);
+        println!("Output:\n{}", redact(&stdout));
     }
 
-    println!("{}", "✅ Helmfile lint passed".green());
+    say!("{}", "✅ Helmfile lint passed".green());
     Ok(())
 }
+
+/// Lines of helm lint output reporting an error or a warning.
+fn is_finding(line: &str) -> bool {
+    line.contains("ERROR") || line.contains("WARNING")
+}
+
+/// Annotates each error and warning line; helm does not report the values
+/// file involved, so they are attached to the helmfile.
+fn annotate_findings(file: &str, stdout: &str, stderr: &str) {
+    for line in stdout
+        .lines()
+        .chain(stderr.lines())
+        .filter(|l| is_finding(l))
+    {
+        let level = if line.contains("ERROR") {
+            Level::Error
+        } else {
+            Level::Warning
+        };
+        ci::annotate(level, file, None, line.trim());
+    }
+}
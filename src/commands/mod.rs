@@ -1,8 +1,16 @@
+pub mod compare;
 pub mod config_cmd;
 pub mod context;
 pub mod deploy;
+pub mod diff_rev;
+pub mod doctor;
 pub mod k8s;
 pub mod lint;
+pub mod outdated;
+pub mod plugins_cmd;
+pub mod render;
 pub mod rollback;
+pub mod secrets_cmd;
 pub mod status;
 pub mod validate;
+pub mod values;
@@ -0,0 +1,259 @@
+use crate::ci::say;
+use crate::helmfile::{Helmfile, Release};
+use crate::tools;
+use crate::utils::{check_command_available, helm_repository_cache, print_command};
+use crate::version::Version;
+use colored::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Deserialize)]
+struct RepoIndex {
+    #[serde(default)]
+    entries: HashMap<String, Vec<IndexEntry>>,
+}
+
+#[derive(Deserialize)]
+struct IndexEntry {
+    version: String,
+}
+
+#[derive(Deserialize)]
+struct HelmListEntry {
+    name: String,
+    namespace: String,
+    chart: String,
+}
+
+struct Row {
+    release: String,
+    chart: String,
+    declared: String,
+    deployed: String,
+    latest: String,
+    notes: Vec<ColoredString>,
+}
+
+pub fn execute(
+    file: &str,
+    release: Option<&str>,
+    repo_cache: Option<&str>,
+    offline: bool,
+    verbose: bool,
+) -> Result<(), String> {
+    say!(
+        "{}",
+        format!("📦 Checking chart versions: {}", file)
+            .cyan()
+            .bold()
+    );
+
+    if !Path::new(file).exists() {
+        return Err(format!("Helmfile not found: {}", file));
+    }
+
+    let helmfile = Helmfile::load(file)?;
+    let releases = helmfile.select_releases(release);
+    if releases.is_empty() {
+        return Err(match release {
+            Some(name) => format!("Release not found in helmfile: {}", name),
+            None => "No releases defined in helmfile".to_string(),
+        });
+    }
+
+    let cache_dir = match repo_cache {
+        Some(dir) => PathBuf::from(dir),
+        None => helm_repository_cache()?,
+    };
+    if verbose {
+        say!("📂 Repository cache: {}", cache_dir.display());
+    }
+
+    let deployed = if offline {
+        say!("🔌 Offline mode: skipping deployed versions");
+        Vec::new()
+    } else {
+        deployed_releases(verbose)
+    };
+
+    let mut indexes: HashMap<String, Option<RepoIndex>> = HashMap::new();
+    let mut rows = Vec::new();
+
+    for rel in releases {
+        let declared = rel.version.clone();
+        let deployed_version = deployed_version(&deployed, rel);
+
+        let repo_chart = rel
+            .repo_chart()
+            .filter(|(repo, _)| !helmfile.is_oci_repository(repo));
+        let latest = match repo_chart {
+            Some((repo, chart)) => {
+                let index = indexes
+                    .entry(repo.to_string())
+                    .or_insert_with(|| load_index(&cache_dir, repo, verbose));
+                index
+                    .as_ref()
+                    .and_then(|idx| latest_version(idx, chart, declared.as_deref()))
+            }
+            None => None,
+        };
+
+        let mut notes = Vec::new();
+        if is_unpinned(declared.as_deref()) {
+            notes.push("unpinned".yellow());
+        }
+
+        let baseline = deployed_version
+            .as_deref()
+            .or(declared.as_deref())
+            .and_then(Version::parse);
+        if let (Some(current), Some(newest)) = (&baseline, &latest) {
+            if newest.major > current.major {
+                notes.push(format!("major {} → {}", current.major, newest.major).red());
+            } else if newest > current {
+                notes.push("update available".cyan());
+            }
+        }
+        if repo_chart.is_none() {
+            notes.push("local/oci chart".dimmed());
+        } else if latest.is_none() {
+            notes.push("not in repo cache".dimmed());
+        }
+
+        rows.push(Row {
+            release: rel.name.clone(),
+            chart: rel.chart.clone(),
+            declared: declared.unwrap_or_else(|| "-".to_string()),
+            deployed: deployed_version.unwrap_or_else(|| "-".to_string()),
+            latest: latest.map_or("-".to_string(), |v| v.to_string()),
+            notes,
+        });
+    }
+
+    print_table(&rows);
+    Ok(())
+}
+
+fn deployed_releases(verbose: bool) -> Vec<HelmListEntry> {
+    if check_command_available("helm").is_err() {
+        say!("⚠️  helm not found, skipping deployed versions");
+        return Vec::new();
+    }
+
+    let mut cmd = tools::command("helm");
+    cmd.args(["list", "--all-namespaces", "--output", "json"]);
+
+    if verbose {
+        print_command(&cmd);
+    }
+
+    match cmd.output() {
+        Ok(output) if output.status.success() => {
+            serde_json::from_slice(&output.stdout).unwrap_or_default()
+        }
+        _ => {
+            say!("⚠️  Could not query deployed releases, skipping deployed versions");
+            Vec::new()
+        }
+    }
+}
+
+fn deployed_version(deployed: &[HelmListEntry], release: &Release) -> Option<String> {
+    let prefix = format!("{}-", release.chart_name());
+    deployed
+        .iter()
+        .find(|d| {
+            d.name == release.name
+                && release
+                    .namespace
+                    .as_deref()
+                    .is_none_or(|ns| ns == d.namespace)
+        })
+        .and_then(|d| d.chart.strip_prefix(&prefix).map(str::to_string))
+}
+
+fn load_index(cache_dir: &Path, repo: &str, verbose: bool) -> Option<RepoIndex> {
+    let path = cache_dir.join(format!("{}-index.yaml", repo));
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(_) => {
+            if verbose {
+                say!("⚠️  No cached index for repository {}", repo);
+            }
+            return None;
+        }
+    };
+
+    match serde_yaml::from_str(&content) {
+        Ok(index) => Some(index),
+        Err(e) => {
+            say!("⚠️  Could not parse {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+fn latest_version(index: &RepoIndex, chart: &str, declared: Option<&str>) -> Option<Version> {
+    // Prereleases are only considered when the helmfile already tracks one.
+    let allow_pre = declared
+        .and_then(Version::parse)
+        .is_some_and(|v| v.is_prerelease());
+
+    index
+        .entries
+        .get(chart)?
+        .iter()
+        .filter_map(|entry| Version::parse(&entry.version))
+        .filter(|v| allow_pre || !v.is_prerelease())
+        .max()
+}
+
+fn is_unpinned(declared: Option<&str>) -> bool {
+    match declared {
+        None => true,
+        Some(v) => {
+            v.contains(['*', '^', '~', '>', '<', ' ', '|'])
+                || v.split('.').any(|p| p.eq_ignore_ascii_case("x"))
+                || Version::parse(v).is_none()
+        }
+    }
+}
+
+fn print_table(rows: &[Row]) {
+    let headers = ["RELEASE", "CHART", "DECLARED", "DEPLOYED", "LATEST"];
+    let columns = |row: &Row| -> [String; 5] {
+        [
+            row.release.clone(),
+            row.chart.clone(),
+            row.declared.clone(),
+            row.deployed.clone(),
+            row.latest.clone(),
+        ]
+    };
+
+    let mut widths = headers.map(str::len);
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(columns(row)) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    let header: Vec<String> = headers
+        .iter()
+        .zip(widths)
+        .map(|(h, w)| format!("{:<w$}", h, w = w))
+        .collect();
+    println!("{}  NOTES", header.join("  ").bright_white().bold());
+
+    for row in rows {
+        let cells: Vec<String> = columns(row)
+            .iter()
+            .zip(widths)
+            .map(|(c, w)| format!("{:<w$}", c, w = w))
+            .collect();
+        let notes: Vec<String> = row.notes.iter().map(|n| n.to_string()).collect();
+        println!("{}  {}", cells.join("  "), notes.join(", "));
+    }
+}
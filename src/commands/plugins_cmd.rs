@@ -0,0 +1,85 @@
+use crate::ci::say;
+use crate::cli::PluginsAction;
+use crate::plugins::{self, REQUIRED_PLUGINS};
+use crate::utils::check_command_available;
+use colored::*;
+
+pub fn execute(action: PluginsAction, verbose: bool) -> Result<(), String> {
+    check_command_available("helm")?;
+
+    match action {
+        PluginsAction::List => list(),
+        PluginsAction::Install { plugin, from } => {
+            install(plugin.as_deref(), from.as_deref(), verbose)
+        }
+    }
+}
+
+fn list() -> Result<(), String> {
+    let installed = plugins::installed()?;
+
+    say!("{}", "🔌 Helm plugins:".cyan().bold());
+    if installed.is_empty() {
+        println!("  No plugins installed");
+    }
+    for plugin in &installed {
+        println!("  {:<20} {}", plugin.name.yellow(), plugin.version);
+    }
+
+    println!();
+    println!("{}", "Required by helmctl:".bright_white().bold());
+    let mut missing = 0;
+    for required in &REQUIRED_PLUGINS {
+        match required.check(&installed) {
+            Ok(version) => say!(
+                "  ✅ {:<17} {} (>= {})",
+                required.name,
+                version,
+                required.minimum
+            ),
+            Err(e) => {
+                missing += 1;
+                say!("  ❌ {:<17} {}", required.name, e.red());
+            }
+        }
+    }
+
+    if missing > 0 {
+        return Err(format!(
+            "{} required plugin(s) missing or outdated. Run: helmctl plugins install",
+            missing
+        ));
+    }
+    Ok(())
+}
+
+fn install(plugin: Option<&str>, from: Option<&str>, verbose: bool) -> Result<(), String> {
+    // An explicitly named plugin is always (re)installed
+    if let Some(name) = plugin {
+        let required = plugins::required(name).ok_or_else(|| {
+            let known: Vec<&str> = REQUIRED_PLUGINS.iter().map(|p| p.name).collect();
+            format!(
+                "Unknown plugin: {}. Plugins managed by helmctl: {}",
+                name,
+                known.join(", ")
+            )
+        })?;
+        return required.install(from, verbose);
+    }
+
+    let installed = plugins::installed()?;
+    let pending: Vec<_> = REQUIRED_PLUGINS
+        .iter()
+        .filter(|p| p.check(&installed).is_err())
+        .collect();
+
+    if pending.is_empty() {
+        say!("{}", "✅ All required helm plugins are installed".green());
+        return Ok(());
+    }
+
+    for required in pending {
+        required.install(None, verbose)?;
+    }
+    Ok(())
+}
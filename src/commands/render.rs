@@ -0,0 +1,141 @@
+use crate::ci::say;
+use crate::commands::validate;
+use colored::*;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+pub fn execute(
+    file: &str,
+    environment: Option<&str>,
+    out_dir: &str,
+    clean: bool,
+    verbose: bool,
+) -> Result<(), String> {
+    say!(
+        "{}",
+        format!("📝 Rendering {} into {}", file, out_dir)
+            .cyan()
+            .bold()
+    );
+
+    if !Path::new(file).exists() {
+        return Err(format!("Helmfile not found: {}", file));
+    }
+    if let Some(env) = environment {
+        say!("📋 Environment: {}", env.yellow());
+    }
+
+    let out_dir = Path::new(out_dir);
+    if clean && out_dir.exists() {
+        check_cleanable(out_dir, file)?;
+    }
+
+    let releases = validate::render_releases(file, environment, verbose)?;
+
+    if clean && out_dir.exists() {
+        let stale = fs::read_dir(out_dir)
+            .map_err(|e| format!("Failed to read {}: {}", out_dir.display(), e))?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir())
+            .filter(|path| {
+                path.file_name()
+                    .is_some_and(|name| !releases.contains_key(&*name.to_string_lossy()))
+            });
+        for dir in stale {
+            clear_release_dir(&dir)?;
+        }
+    }
+
+    for (release, objects) in &releases {
+        // Clear the release directory so deleted objects disappear
+        let dir = out_dir.join(release);
+        clear_release_dir(&dir)?;
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+        let mut written = HashSet::new();
+        for object in objects {
+            let mut name = file_name(object, false);
+            if !written.insert(name.clone()) {
+                name = file_name(object, true);
+                written.insert(name.clone());
+            }
+            // serde_json keeps keys sorted, which makes the output stable
+            let content = serde_yaml::to_string(object)
+                .map_err(|e| format!("Failed to serialize {}: {}", name, e))?;
+            let path = dir.join(&name);
+            fs::write(&path, content)
+                .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        }
+
+        say!(
+            "✅ {}: {} object(s) in {}",
+            release.green(),
+            objects.len(),
+            dir.display()
+        );
+    }
+
+    if releases.is_empty() {
+        say!("⚠️  No releases rendered");
+    }
+
+    Ok(())
+}
+
+/// Refuses to clean the current directory, one of its parents or a
+/// directory holding the helmfile, which are not render output.
+fn check_cleanable(out_dir: &Path, file: &str) -> Result<(), String> {
+    let canonical = |path: &Path| {
+        path.canonicalize()
+            .map_err(|e| format!("Failed to resolve {}: {}", path.display(), e))
+    };
+    let out = canonical(out_dir)?;
+    let cwd = env::current_dir()
+        .map_err(|e| format!("Failed to determine the current directory: {}", e))
+        .and_then(|cwd| canonical(&cwd))?;
+    let helmfile = canonical(Path::new(file))?;
+
+    if cwd.starts_with(&out) || helmfile.starts_with(&out) {
+        return Err(format!(
+            "Refusing to clean {}: it contains the current directory or the helmfile",
+            out_dir.display()
+        ));
+    }
+    Ok(())
+}
+
+/// Removes the `.yaml` files of an earlier render from a release
+/// directory, and the directory once empty. Anything else is left alone.
+fn clear_release_dir(dir: &Path) -> Result<(), String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(());
+    };
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "yaml") {
+            fs::remove_file(&path)
+                .map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+        }
+    }
+    // Fails while other files remain, which is intended
+    let _ = fs::remove_dir(dir);
+    Ok(())
+}
+
+/// `<kind>-<name>.yaml`, or `<kind>-<namespace>-<name>.yaml` when the same
+/// kind and name exist in several namespaces.
+fn file_name(object: &Value, with_namespace: bool) -> String {
+    let kind = object["kind"].as_str().unwrap_or_default().to_lowercase();
+    let name = object["metadata"]["name"].as_str().unwrap_or_default();
+    let namespace = object["metadata"]["namespace"].as_str();
+
+    let stem = match namespace {
+        Some(ns) if with_namespace => format!("{}-{}-{}", kind, ns, name),
+        _ => format!("{}-{}", kind, name),
+    };
+    format!("{}.yaml", stem.replace(['/', ':'], "_"))
+}
@@ -1,8 +1,8 @@
-use crate::utils::{check_command_available, set_kubectl_context};
+use crate::ci::say;
+use crate::tools;
+use crate::utils::{check_command_available, confirm, print_command, set_kubectl_context};
 use colored::*;
-use std::io::{self, Write};
 use std::path::Path;
-use std::process::Command;
 
 pub fn execute(
     file: &str,
@@ -12,7 +12,7 @@ pub fn execute(
     context: Option<&str>,
     verbose: bool,
 ) -> Result<(), String> {
-    println!("{}", "🔄 Rolling back Helmfile releases...".cyan().bold());
+    say!("{}", "🔄 Rolling back Helmfile releases...".cyan().bold());
 
     if !Path::new(file).exists() {
         return Err(format!("Helmfile not found: {}", file));
@@ -25,25 +25,19 @@ pub fn execute(
         set_kubectl_context(ctx, verbose)?;
     }
 
-    // Interactive confirmation
-    print!(
-        "⚠️  You are about to rollback releases. This action cannot be undone. Continue? (y/N): "
-    );
-    io::stdout().flush().unwrap();
-    let mut input = String::new();
-    io::stdin().read_line(&mut input).unwrap();
-    if !input.trim().to_lowercase().starts_with('y') {
+    if !confirm("⚠️  You are about to rollback releases. This action cannot be undone. Continue?")?
+    {
         return Err("Rollback cancelled by user".to_string());
     }
 
     // For individual release rollback with specific revision
     if let (Some(rel), Some(rev)) = (release, revision) {
-        println!("🔄 Rolling back {} to revision {}...", rel, rev);
-        let mut helm_cmd = Command::new("helm");
+        say!("🔄 Rolling back {} to revision {}...", rel, rev);
+        let mut helm_cmd = tools::command("helm");
         helm_cmd.args(["rollback", rel, &rev.to_string()]);
 
         if verbose {
-            println!("🔧 Command: {:?}", helm_cmd);
+            print_command(&helm_cmd);
         }
 
         let status = helm_cmd
@@ -55,26 +49,26 @@ pub fn execute(
         }
     } else {
         // Use helmfile's destroy and redeploy approach
-        println!("🗑️  Destroying current releases...");
+        say!("🗑️  Destroying current releases...");
 
         // Create destroy command
-        let mut destroy_cmd = Command::new("helmfile");
+        let mut destroy_cmd = tools::command("helmfile");
         destroy_cmd.arg("-f").arg(file);
 
         if let Some(env) = environment {
             destroy_cmd.arg("-e").arg(env);
-            println!("📋 Environment: {}", env.yellow());
+            say!("📋 Environment: {}", env.yellow());
         }
 
         if let Some(rel) = release {
             destroy_cmd.arg("--selector").arg(format!("name={}", rel));
-            println!("📦 Release: {}", rel.yellow());
+            say!("📦 Release: {}", rel.yellow());
         }
 
         destroy_cmd.arg("destroy");
 
         if verbose {
-            println!("🔧 Command: {:?}", destroy_cmd);
+            print_command(&destroy_cmd);
         }
 
         let status = destroy_cmd
@@ -85,10 +79,10 @@ pub fn execute(
             return Err("Failed to destroy releases".to_string());
         }
 
-        println!("🚀 Redeploying from helmfile...");
+        say!("🚀 Redeploying from helmfile...");
 
         // Create redeploy command
-        let mut redeploy_cmd = Command::new("helmfile");
+        let mut redeploy_cmd = tools::command("helmfile");
         redeploy_cmd.arg("-f").arg(file);
 
         if let Some(env) = environment {
@@ -102,7 +96,7 @@ pub fn execute(
         redeploy_cmd.arg("sync");
 
         if verbose {
-            println!("🔧 Command: {:?}", redeploy_cmd);
+            print_command(&redeploy_cmd);
         }
 
         let status = redeploy_cmd
@@ -114,6 +108,6 @@ pub fn execute(
         }
     }
 
-    println!("{}", "✅ Rollback completed".green());
+    say!("{}", "✅ Rollback completed".green());
     Ok(())
 }
@@ -0,0 +1,153 @@
+use crate::ci::say;
+use crate::cli::SecretsAction;
+use crate::secrets::{sops_keys, verify_helmfile};
+use crate::tools;
+use crate::utils::{check_command_available, print_command};
+use colored::*;
+use std::path::Path;
+use std::process::Command;
+
+pub fn execute(action: SecretsAction, verbose: bool) -> Result<(), String> {
+    match action {
+        SecretsAction::Check {
+            file,
+            environment,
+            release,
+        } => check(&file, environment.as_deref(), release.as_deref(), verbose),
+        SecretsAction::View { file } => view(&file, verbose),
+        SecretsAction::Edit { file } => edit(&file, verbose),
+        SecretsAction::Rotate {
+            file,
+            add_age,
+            rm_age,
+        } => rotate(&file, add_age.as_deref(), rm_age.as_deref(), verbose),
+    }
+}
+
+fn check(
+    file: &str,
+    environment: Option<&str>,
+    release: Option<&str>,
+    verbose: bool,
+) -> Result<(), String> {
+    say!(
+        "{}",
+        format!("🔐 Checking encrypted values: {}", file)
+            .cyan()
+            .bold()
+    );
+
+    if !Path::new(file).exists() {
+        return Err(format!("Helmfile not found: {}", file));
+    }
+
+    let checks = verify_helmfile(file, environment, release, verbose)?;
+    if checks.is_empty() {
+        println!("No encrypted values files referenced");
+        return Ok(());
+    }
+
+    let mut failed = 0;
+    for check in &checks {
+        let owner = check.release.as_deref().unwrap_or("environment");
+        match &check.error {
+            None => say!("  ✅ {} ({})", check.path.display(), owner),
+            Some(e) => {
+                failed += 1;
+                say!("  ❌ {} ({}): {}", check.path.display(), owner, e.red());
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(format!(
+            "{} of {} encrypted file(s) cannot be decrypted",
+            failed,
+            checks.len()
+        ));
+    }
+
+    say!("{}", "✅ All encrypted values can be decrypted".green());
+    Ok(())
+}
+
+fn view(file: &str, verbose: bool) -> Result<(), String> {
+    require_encrypted(file)?;
+
+    // Decrypted output goes straight to the terminal and is never captured
+    let mut cmd = tools::command("sops");
+    cmd.arg("--decrypt").arg(file);
+    run_sops(cmd, verbose)
+}
+
+fn edit(file: &str, verbose: bool) -> Result<(), String> {
+    require_encrypted(file)?;
+
+    let mut cmd = tools::command("sops");
+    cmd.arg(file);
+    run_sops(cmd, verbose)?;
+
+    say!("{}", format!("✅ Saved {}", file).green());
+    Ok(())
+}
+
+fn rotate(
+    file: &str,
+    add_age: Option<&str>,
+    rm_age: Option<&str>,
+    verbose: bool,
+) -> Result<(), String> {
+    require_encrypted(file)?;
+
+    say!(
+        "{}",
+        format!("🔄 Rotating data key: {}", file).cyan().bold()
+    );
+
+    let mut cmd = tools::command("sops");
+    cmd.args(["--rotate", "--in-place"]);
+
+    if let Some(recipient) = add_age {
+        cmd.arg("--add-age").arg(recipient);
+        say!("➕ Adding age recipient: {}", recipient.yellow());
+    }
+
+    if let Some(recipient) = rm_age {
+        cmd.arg("--rm-age").arg(recipient);
+        say!("➖ Removing age recipient: {}", recipient.yellow());
+    }
+
+    cmd.arg(file);
+    run_sops(cmd, verbose)?;
+
+    say!("{}", "✅ Data key rotated".green());
+    Ok(())
+}
+
+fn require_encrypted(file: &str) -> Result<(), String> {
+    if !Path::new(file).exists() {
+        return Err(format!("File not found: {}", file));
+    }
+
+    if sops_keys(Path::new(file)).is_none() {
+        return Err(format!("Not a sops-encrypted file: {}", file));
+    }
+
+    check_command_available("sops")
+}
+
+fn run_sops(mut cmd: Command, verbose: bool) -> Result<(), String> {
+    if verbose {
+        print_command(&cmd);
+    }
+
+    let status = cmd
+        .status()
+        .map_err(|e| format!("Failed to execute sops: {}", e))?;
+
+    if !status.success() {
+        return Err("sops operation failed".to_string());
+    }
+
+    Ok(())
+}
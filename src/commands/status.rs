@@ -1,17 +1,77 @@
-use crate::utils::{check_command_available, set_kubectl_context};
+use crate::ci::{self, say};
+use crate::cluster::{self, Resource};
+use crate::manifest;
+use crate::redact::redact;
+use crate::tools;
+use crate::utils::{check_command_available, print_command, set_kubectl_context};
+use chrono::Local;
 use colored::*;
+use std::collections::HashMap;
+use std::io::{self, IsTerminal, Write};
 use std::path::Path;
-use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
 
+struct ReleaseHealth {
+    name: String,
+    namespace: Option<String>,
+    status: String,
+    /// Whether the release manifest creates pods at all
+    has_workloads: bool,
+    pods: Vec<PodHealth>,
+}
+
+struct PodHealth {
+    name: String,
+    phase: String,
+    ready: usize,
+    total: usize,
+    restarts: u64,
+}
+
+/// Kinds that create pods and must have them before a release counts as
+/// healthy. CronJobs are left out since they are idle between runs.
+const WORKLOAD_KINDS: [&str; 6] = [
+    "Deployment",
+    "StatefulSet",
+    "DaemonSet",
+    "ReplicaSet",
+    "Job",
+    "Pod",
+];
+
+impl ReleaseHealth {
+    /// Deployed, and either without workloads or with at least one pod,
+    /// all of them ready, so a watch cannot succeed before anything is
+    /// scheduled.
+    fn is_healthy(&self) -> bool {
+        self.status == "deployed"
+            && (!self.has_workloads || !self.pods.is_empty())
+            && self.pods.iter().all(PodHealth::is_healthy)
+    }
+}
+
+impl PodHealth {
+    fn is_healthy(&self) -> bool {
+        self.phase == "Succeeded"
+            || (self.phase == "Running" && self.total > 0 && self.ready == self.total)
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn execute(
     file: &str,
     environment: Option<&str>,
     release: Option<&str>,
     context: Option<&str>,
+    namespace: Option<&str>,
     detailed: bool,
+    watch: bool,
+    interval: u64,
+    timeout: u32,
     verbose: bool,
 ) -> Result<(), String> {
-    println!("{}", "📊 Checking release status...".cyan().bold());
+    say!("{}", "📊 Checking release status...".cyan().bold());
 
     if !Path::new(file).exists() {
         return Err(format!("Helmfile not found: {}", file));
@@ -20,26 +80,41 @@ pub fn execute(
     check_command_available("helmfile")?;
 
     if let Some(ctx) = context {
-        set_kubectl_context(ctx, verbose)?;
+        if tools::resolve("kubectl").is_some() {
+            set_kubectl_context(ctx, verbose)?;
+        }
+        cluster::select_context(ctx);
+    }
+
+    if watch {
+        return watch_releases(
+            file,
+            environment,
+            release,
+            namespace,
+            interval,
+            timeout,
+            verbose,
+        );
     }
 
-    let mut cmd = Command::new("helmfile");
+    let mut cmd = tools::command("helmfile");
     cmd.arg("-f").arg(file);
 
     if let Some(env) = environment {
         cmd.arg("-e").arg(env);
-        println!("📋 Environment: {}", env.yellow());
+        say!("📋 Environment: {}", env.yellow());
     }
 
     if let Some(rel) = release {
         cmd.arg("--selector").arg(format!("name={}", rel));
-        println!("📦 Release: {}", rel.yellow());
+        say!("📦 Release: {}", rel.yellow());
     }
 
     cmd.arg("status");
 
     if verbose {
-        println!("🔧 Command: {:?}", cmd);
+        print_command(&cmd);
     }
 
     let output = cmd
@@ -52,31 +127,33 @@ pub fn execute(
     }
 
     let stdout = String::from_utf8_lossy(&output.stdout);
-    println!("{}", stdout);
+    println!("{}", redact(&stdout));
 
     if detailed {
-        println!("\n{}", "🔍 Detailed Kubernetes status:".yellow().bold());
-        let mut kubectl_cmd = Command::new("kubectl");
+        say!("\n{}", "🔍 Detailed Kubernetes status:".yellow().bold());
+        // Without a namespace, try the one matching the environment
+        let namespace = namespace.or(environment);
+
+        let mut kubectl_cmd = tools::command("kubectl");
         kubectl_cmd.args(["get", "all", "-o", "wide"]);
 
-        if let Some(env) = environment {
-            // Try to get resources from namespace if it matches environment
-            kubectl_cmd.args(["-n", env]);
+        if let Some(ns) = namespace {
+            kubectl_cmd.args(["-n", ns]);
         }
 
         if verbose {
-            println!("🔧 Command: {:?}", kubectl_cmd);
+            print_command(&kubectl_cmd);
         }
 
         let _ = kubectl_cmd.status();
 
         // Also show pod status
-        println!("\n{}", "🚀 Pod Status:".yellow().bold());
-        let mut pod_cmd = Command::new("kubectl");
+        say!("\n{}", "🚀 Pod Status:".yellow().bold());
+        let mut pod_cmd = tools::command("kubectl");
         pod_cmd.args(["get", "pods", "-o", "wide"]);
 
-        if let Some(env) = environment {
-            pod_cmd.args(["-n", env]);
+        if let Some(ns) = namespace {
+            pod_cmd.args(["-n", ns]);
         }
 
         let _ = pod_cmd.status();
@@ -84,3 +161,308 @@ pub fn execute(
 
     Ok(())
 }
+
+fn watch_releases(
+    file: &str,
+    environment: Option<&str>,
+    release: Option<&str>,
+    namespace: Option<&str>,
+    interval: u64,
+    timeout: u32,
+    verbose: bool,
+) -> Result<(), String> {
+    if !cluster::native_available() {
+        check_command_available("kubectl")?;
+    }
+
+    let releases = list_releases(file, environment, release, verbose)?;
+    if releases.is_empty() {
+        return Err("No installed releases selected".to_string());
+    }
+
+    let start = Instant::now();
+    let deadline = Duration::from_secs(timeout as u64);
+    let mut previous: HashMap<String, String> = HashMap::new();
+
+    // The manifest does not change while watching, so look it up once
+    let releases: Vec<(String, Option<&str>, bool)> = releases
+        .iter()
+        .map(|(name, ns)| {
+            let ns = ns.as_deref().or(namespace);
+            (name.clone(), ns, has_workloads(name, ns, verbose))
+        })
+        .collect();
+
+    // Redrawing only makes sense on a terminal; logs get one snapshot
+    // after another
+    let redraw = io::stdout().is_terminal() && !ci::enabled();
+
+    loop {
+        let health: Vec<ReleaseHealth> = releases
+            .iter()
+            .map(|(name, ns, workloads)| release_health(name, *ns, *workloads))
+            .collect();
+
+        if redraw {
+            // Clear the screen and redraw from the top-left corner
+            print!("\x1B[2J\x1B[H");
+        } else if !previous.is_empty() {
+            println!();
+        }
+        say!(
+            "{}",
+            format!(
+                "📊 Watching {} release(s) — {} (elapsed {}s, timeout {}s, every {}s)",
+                releases.len(),
+                Local::now().format("%H:%M:%S"),
+                start.elapsed().as_secs(),
+                timeout,
+                interval
+            )
+            .cyan()
+            .bold()
+        );
+        previous = render_health(&health, &previous);
+        io::stdout().flush().map_err(|e| e.to_string())?;
+
+        let pending: Vec<&str> = health
+            .iter()
+            .filter(|h| !h.is_healthy())
+            .map(|h| h.name.as_str())
+            .collect();
+
+        if pending.is_empty() {
+            say!("\n{}", "✅ All releases are healthy".green());
+            return Ok(());
+        }
+
+        if start.elapsed() >= deadline {
+            return Err(format!(
+                "Timed out after {}s waiting for releases to become healthy: {}",
+                timeout,
+                pending.join(", ")
+            ));
+        }
+
+        let remaining = deadline.saturating_sub(start.elapsed());
+        thread::sleep(Duration::from_secs(interval.max(1)).min(remaining));
+    }
+}
+
+/// Installed releases selected from the helmfile, as (name, namespace).
+fn list_releases(
+    file: &str,
+    environment: Option<&str>,
+    release: Option<&str>,
+    verbose: bool,
+) -> Result<Vec<(String, Option<String>)>, String> {
+    let mut cmd = tools::command("helmfile");
+    cmd.arg("-f").arg(file);
+
+    if let Some(env) = environment {
+        cmd.arg("-e").arg(env);
+    }
+
+    if let Some(rel) = release {
+        cmd.arg("--selector").arg(format!("name={}", rel));
+    }
+
+    cmd.args(["list", "--output", "json"]);
+
+    if verbose {
+        print_command(&cmd);
+    }
+
+    let output = cmd
+        .output()
+        .map_err(|e| format!("Failed to execute helmfile list: {}", e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("Failed to list releases:\n{}", stderr));
+    }
+
+    let entries: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("Failed to parse helmfile list output: {}", e))?;
+
+    // Older helmfile versions report booleans as strings
+    let is_true = |v: &serde_json::Value| v.as_bool().unwrap_or_else(|| v == "true");
+
+    Ok(entries
+        .iter()
+        .filter(|e| is_true(&e["enabled"]) && is_true(&e["installed"]))
+        .filter_map(|e| {
+            let name = e["name"].as_str()?.to_string();
+            let namespace = e["namespace"]
+                .as_str()
+                .filter(|ns| !ns.is_empty())
+                .map(str::to_string);
+            Some((name, namespace))
+        })
+        .collect())
+}
+
+/// Whether the release's manifest contains kinds that create pods. When
+/// the manifest cannot be read, pods are expected to be safe.
+fn has_workloads(name: &str, namespace: Option<&str>, verbose: bool) -> bool {
+    if tools::resolve("helm").is_none() {
+        return true;
+    }
+
+    let mut cmd = tools::command("helm");
+    cmd.args(["get", "manifest", name]);
+    if let Some(ns) = namespace {
+        cmd.args(["--namespace", ns]);
+    }
+    if verbose {
+        print_command(&cmd);
+    }
+
+    let Ok(output) = cmd.output() else {
+        return true;
+    };
+    if !output.status.success() {
+        return true;
+    }
+    match manifest::parse(&String::from_utf8_lossy(&output.stdout)) {
+        Ok(objects) => objects
+            .iter()
+            .any(|o| WORKLOAD_KINDS.contains(&o.kind.as_str())),
+        Err(_) => true,
+    }
+}
+
+fn release_health(name: &str, namespace: Option<&str>, has_workloads: bool) -> ReleaseHealth {
+    // Release secrets cover the default storage driver; other drivers
+    // are only visible through helm itself
+    let status = match cluster::release_status(name, namespace) {
+        Ok(Some(status)) => status,
+        _ => helm_release_status(name, namespace),
+    };
+
+    let pods = cluster::list(
+        Resource::Pods,
+        namespace,
+        Some(&format!("app.kubernetes.io/instance={}", name)),
+        None,
+    )
+    .map(|items| parse_pods(&items))
+    .unwrap_or_default();
+
+    ReleaseHealth {
+        name: name.to_string(),
+        namespace: namespace.map(str::to_string),
+        status,
+        has_workloads,
+        pods,
+    }
+}
+
+fn helm_release_status(name: &str, namespace: Option<&str>) -> String {
+    if tools::resolve("helm").is_none() {
+        return "not found".to_string();
+    }
+
+    let mut cmd = tools::command("helm");
+    cmd.args(["status", name, "--output", "json"]);
+    if let Some(ns) = namespace {
+        cmd.args(["--namespace", ns]);
+    }
+
+    match cmd.output() {
+        Ok(output) if output.status.success() => {
+            serde_json::from_slice::<serde_json::Value>(&output.stdout)
+                .ok()
+                .and_then(|v| v["info"]["status"].as_str().map(str::to_string))
+                .unwrap_or_else(|| "unknown".to_string())
+        }
+        _ => "not found".to_string(),
+    }
+}
+
+fn parse_pods(items: &[serde_json::Value]) -> Vec<PodHealth> {
+    items
+        .iter()
+        .map(|pod| {
+            let containers = pod["status"]["containerStatuses"]
+                .as_array()
+                .cloned()
+                .unwrap_or_default();
+            PodHealth {
+                name: pod["metadata"]["name"].as_str().unwrap_or("?").to_string(),
+                phase: pod["status"]["phase"]
+                    .as_str()
+                    .unwrap_or("Unknown")
+                    .to_string(),
+                ready: containers
+                    .iter()
+                    .filter(|c| c["ready"].as_bool().unwrap_or(false))
+                    .count(),
+                total: containers.len(),
+                restarts: containers
+                    .iter()
+                    .filter_map(|c| c["restartCount"].as_u64())
+                    .sum(),
+            }
+        })
+        .collect()
+}
+
+/// Prints the current health snapshot, highlighting lines that changed
+/// since the previous refresh, and returns the snapshot for the next one.
+fn render_health(
+    health: &[ReleaseHealth],
+    previous: &HashMap<String, String>,
+) -> HashMap<String, String> {
+    let mut current = HashMap::new();
+
+    let mut emit = |key: String, line: String, healthy: bool| {
+        let changed = !previous.is_empty() && previous.get(&key) != Some(&line);
+        let styled = if changed {
+            format!("{} {}", "*".yellow().bold(), line.yellow().bold())
+        } else if healthy {
+            format!("  {}", line.green())
+        } else {
+            format!("  {}", line)
+        };
+        say!("{}", styled);
+        current.insert(key, line);
+    };
+
+    for release in health {
+        println!();
+        let icon = if release.is_healthy() { "✅" } else { "⏳" };
+        let pods = if !release.pods.is_empty() {
+            ""
+        } else if release.has_workloads {
+            ", no pods yet"
+        } else {
+            ", no workloads"
+        };
+        emit(
+            release.name.clone(),
+            format!(
+                "{} {} ({}) — {}{}",
+                icon,
+                release.name,
+                release.namespace.as_deref().unwrap_or("default"),
+                release.status,
+                pods
+            ),
+            release.is_healthy(),
+        );
+
+        for pod in &release.pods {
+            emit(
+                format!("{}/{}", release.name, pod.name),
+                format!(
+                    "    {:<50} {:<10} {}/{} ready, {} restarts",
+                    pod.name, pod.phase, pod.ready, pod.total, pod.restarts
+                ),
+                pod.is_healthy(),
+            );
+        }
+    }
+
+    current
+}
@@ -1,8 +1,14 @@
-use crate::utils::check_command_available;
+use crate::ci::{self, say, Level};
+use crate::manifest;
+use crate::redact::redact;
+use crate::tools;
+use crate::utils::{check_command_available, print_command, scratch_dir};
 use colored::*;
+use serde_json::Value;
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::Path;
-use std::process::{Command, Stdio};
+use std::process::Stdio;
 
 pub fn execute(
     file: &str,
@@ -10,7 +16,7 @@ pub fn execute(
     syntax_only: bool,
     verbose: bool,
 ) -> Result<(), String> {
-    println!(
+    say!(
         "{}",
         format!("🔍 Validating Helmfile templates: {}", file)
             .cyan()
@@ -31,15 +37,20 @@ pub fn execute(
 }
 
 fn validate_yaml_syntax(file: &str) -> Result<(), String> {
-    println!("🔍 Validating YAML syntax...");
+    say!("🔍 Validating YAML syntax...");
 
     match fs::read_to_string(file) {
         Ok(content) => {
             // Split by document separator and validate each document
             let documents: Vec<&str> = content.split("---").collect();
+            // Lines before the current document, to annotate file lines
+            let mut offset = 0;
 
             for (i, doc) in documents.iter().enumerate() {
                 let trimmed = doc.trim();
+                let leading = doc.len() - doc.trim_start().len();
+                let start = offset + doc[..leading].matches('\n').count();
+                offset += doc.matches('\n').count();
                 if trimmed.is_empty() {
                     continue;
                 }
@@ -47,16 +58,18 @@ fn validate_yaml_syntax(file: &str) -> Result<(), String> {
                 match serde_yaml::from_str::<serde_yaml::Value>(trimmed) {
                     Ok(_) => {
                         if documents.len() > 1 {
-                            println!("✅ Document {} syntax is valid", i + 1);
+                            say!("✅ Document {} syntax is valid", i + 1);
                         }
                     }
                     Err(e) => {
+                        let line = e.location().map(|l| start + l.line());
+                        ci::annotate(Level::Error, file, line, &e.to_string());
                         return Err(format!("YAML syntax error in document {}: {}", i + 1, e));
                     }
                 }
             }
 
-            println!("{}", "✅ All YAML syntax is valid".green());
+            say!("{}", "✅ All YAML syntax is valid".green());
             Ok(())
         }
         Err(e) => Err(format!("Failed to read file: {}", e)),
@@ -64,22 +77,74 @@ fn validate_yaml_syntax(file: &str) -> Result<(), String> {
 }
 
 fn validate_templates(file: &str, environment: Option<&str>, verbose: bool) -> Result<(), String> {
-    check_command_available("helmfile")?;
+    say!("🔍 Validating templates with rendering...");
+
+    if let Some(env) = environment {
+        say!("📋 Environment: {}", env.yellow());
+    }
+
+    let output = helmfile_template(file, environment, None, verbose).map_err(|e| {
+        let first = e.lines().find(|l| !l.trim().is_empty());
+        ci::annotate(
+            Level::Error,
+            file,
+            None,
+            first.unwrap_or("rendering failed"),
+        );
+        format!("Template validation failed:\n{}", e)
+    })?;
+
+    let stdout = String::from_utf8_lossy(&output);
+
+    // Check if the output contains valid YAML
+    if !stdout.trim().is_empty() {
+        // Try to parse the rendered output as YAML
+        match serde_yaml::from_str::<serde_yaml::Value>(&stdout) {
+            Ok(_) => say!("{}", "✅ Rendered templates are valid YAML".green()),
+            Err(e) => {
+                if verbose {
+                    say!("⚠️  Warning: Rendered output may not be valid YAML: {}", e);
+                    let preview: String = redact(&stdout).chars().take(500).collect();
+                    println!("Output preview:\n{}", preview);
+                }
+            }
+        }
+    }
 
-    println!("🔍 Validating templates with rendering...");
+    say!("{}", "✅ Templates validation completed".green());
+    Ok(())
+}
 
-    let mut cmd = Command::new("helmfile");
+/// Runs `helmfile template` without touching the cluster or dependencies.
+/// With `output_dir`, helmfile writes each release's manifests under
+/// `<output_dir>/<release>` instead of stdout. Returns stdout, or stderr
+/// as the error.
+fn helmfile_template(
+    file: &str,
+    environment: Option<&str>,
+    output_dir: Option<&Path>,
+    verbose: bool,
+) -> Result<Vec<u8>, String> {
+    check_command_available("helmfile")?;
+
+    let mut cmd = tools::command("helmfile");
     cmd.arg("-f").arg(file);
 
     if let Some(env) = environment {
         cmd.arg("-e").arg(env);
-        println!("📋 Environment: {}", env.yellow());
     }
 
     cmd.args(["template", "--skip-deps"]);
 
+    if let Some(dir) = output_dir {
+        cmd.arg("--output-dir").arg(dir).args([
+            "--output-dir-template",
+            "{{ .OutputDir }}/{{ .Release.Name }}",
+        ]);
+    }
+
     if verbose {
-        println!("🔧 Command: {:?}", cmd);
+        print_command(&cmd);
     }
 
     let output = cmd
@@ -89,29 +154,50 @@ fn validate_templates(file: &str, environment: Option<&str>, verbose: bool) -> R
         .map_err(|e| format!("Failed to execute helmfile template: {}", e))?;
 
     if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Template validation failed:\n{}", stderr));
+        return Err(String::from_utf8_lossy(&output.stderr).into_owned());
     }
+    Ok(output.stdout)
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
+/// Renders every release of `file` and returns its objects by release
+/// name, ordered by kind, namespace and name.
+pub fn render_releases(
+    file: &str,
+    environment: Option<&str>,
+    verbose: bool,
+) -> Result<BTreeMap<String, Vec<Value>>, String> {
+    let dir = scratch_dir(&format!("render-{}", environment.unwrap_or("default")))?;
+    let result = helmfile_template(file, environment, Some(&dir), verbose)
+        .map_err(|e| format!("Template rendering failed:\n{}", e))
+        .and_then(|_| read_releases(&dir));
+    let _ = fs::remove_dir_all(&dir);
+    result
+}
 
-    // Check if the output contains valid YAML
-    if !stdout.trim().is_empty() {
-        // Try to parse the rendered output as YAML
-        match serde_yaml::from_str::<serde_yaml::Value>(&stdout) {
-            Ok(_) => println!("{}", "✅ Rendered templates are valid YAML".green()),
-            Err(e) => {
-                if verbose {
-                    println!("⚠️  Warning: Rendered output may not be valid YAML: {}", e);
-                    println!(
-                        "Output preview:\n{}",
-                        &stdout[..std::cmp::min(500, stdout.len())]
-                    );
-                }
-            }
+fn read_releases(dir: &Path) -> Result<BTreeMap<String, Vec<Value>>, String> {
+    let mut releases = BTreeMap::new();
+    let entries =
+        fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        if !path.is_dir() {
+            continue;
         }
+        let mut objects = manifest::load_documents(&path)?;
+        objects.sort_by_key(object_key);
+        releases.insert(entry.file_name().to_string_lossy().into_owned(), objects);
     }
 
-    println!("{}", "✅ Templates validation completed".green());
-    Ok(())
+    Ok(releases)
+}
+
+/// Sort key of a rendered object: kind, namespace, name.
+pub fn object_key(object: &Value) -> (String, String, String) {
+    let text = |v: &Value| v.as_str().unwrap_or_default().to_string();
+    (
+        text(&object["kind"]),
+        text(&object["metadata"]["namespace"]),
+        text(&object["metadata"]["name"]),
+    )
 }
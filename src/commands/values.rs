@@ -0,0 +1,746 @@
+use crate::ci::say;
+use crate::cli::{OutputFormat, ValuesBaseline};
+use crate::helmfile::{self, Helmfile, Release};
+use crate::redact::{self, redact};
+use crate::schema::{self, Segment};
+use crate::tools;
+use crate::utils::{check_command_available, print_command, scratch_dir};
+use colored::*;
+use serde::Serialize;
+use serde_yaml::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// Where a value was set.
+#[derive(Clone)]
+struct Source {
+    file: String,
+    line: Option<usize>,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}", self.file, line),
+            None => write!(f, "{}", self.file),
+        }
+    }
+}
+
+/// Values merged the way helm merges them, remembering the source of
+/// every leaf key.
+struct Merged {
+    values: Value,
+    sources: BTreeMap<String, Source>,
+}
+
+#[derive(Serialize)]
+struct Entry {
+    key: String,
+    value: serde_json::Value,
+    source: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    baseline: Option<serde_json::Value>,
+}
+
+#[derive(Serialize)]
+struct Report {
+    release: String,
+    environment: String,
+    values: serde_json::Value,
+    entries: Vec<Entry>,
+    /// Keys only present in the compared values
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    baseline_only: Vec<Entry>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute(
+    file: &str,
+    release: &str,
+    environment: Option<&str>,
+    set: &[String],
+    compare: Option<ValuesBaseline>,
+    output: OutputFormat,
+    verbose: bool,
+) -> Result<(), String> {
+    let text = output == OutputFormat::Text;
+
+    if !Path::new(file).exists() {
+        return Err(format!("Helmfile not found: {}", file));
+    }
+
+    let env_name = environment.unwrap_or("default");
+    let helmfile = Helmfile::load(file)?;
+    let (index, rel) = helmfile
+        .releases
+        .iter()
+        .enumerate()
+        .find(|(_, r)| r.name == release)
+        .ok_or_else(|| format!("Release not found in helmfile: {}", release))?;
+
+    if text {
+        say!(
+            "{}",
+            format!("📋 Values for release {} ({})", release, env_name)
+                .cyan()
+                .bold()
+        );
+    }
+
+    let (mut merged, templated) = merge_files(file, index, rel, env_name)?;
+    let rendered = render_values(file, environment, rel, verbose && text);
+    if let Ok(Some(rendered)) = rendered {
+        merged.adopt(rendered, &templated);
+    } else if text {
+        if let Err(e) = rendered {
+            say!("⚠️  {}", e);
+        }
+        for entry in &templated {
+            say!("⚠️  Skipping templated values entry: {}", entry.yellow());
+        }
+    }
+    apply_sets(&mut merged, file, index, rel, set)?;
+    mask(&mut merged.values);
+
+    let baseline = match compare {
+        Some(baseline) => {
+            let mut values = load_baseline(baseline, file, rel, verbose && text)?;
+            mask(&mut values);
+            Some(values)
+        }
+        None => None,
+    };
+    let baseline_leaves: BTreeMap<String, &Value> = baseline
+        .as_ref()
+        .map(|b| leaves(b).into_iter().collect())
+        .unwrap_or_default();
+
+    let entries: Vec<Entry> = leaves(&merged.values)
+        .into_iter()
+        .map(|(key, value)| Entry {
+            value: to_json(value),
+            source: merged
+                .sources
+                .get(&key)
+                .map(Source::to_string)
+                .unwrap_or_default(),
+            baseline: baseline.as_ref().map(|_| {
+                baseline_leaves
+                    .get(&key)
+                    .map(|v| to_json(v))
+                    .unwrap_or_default()
+            }),
+            key,
+        })
+        .collect();
+
+    let baseline_only: Vec<Entry> = baseline_leaves
+        .iter()
+        .filter(|(key, _)| !merged.sources.contains_key(*key))
+        .map(|(key, value)| Entry {
+            key: key.clone(),
+            value: serde_json::Value::Null,
+            source: String::new(),
+            baseline: Some(to_json(value)),
+        })
+        .collect();
+
+    let report = Report {
+        release: release.to_string(),
+        environment: env_name.to_string(),
+        values: to_json(&merged.values),
+        entries,
+        baseline_only,
+    };
+
+    match output {
+        OutputFormat::Json => {
+            let json = serde_json::to_string_pretty(&report)
+                .map_err(|e| format!("Failed to serialize values: {}", e))?;
+            println!("{}", json);
+        }
+        OutputFormat::Text => print_report(&report, compare),
+    }
+    Ok(())
+}
+
+/// Line of a dotted path in the helmfile. Lookups follow indentation from
+/// the top of the file, which is only reliable for single-document
+/// helmfiles.
+fn locate_in_helmfile(file: &str, path: &str) -> Option<usize> {
+    let source = fs::read_to_string(file).ok()?;
+    if source.lines().any(|l| l.trim_end() == "---") {
+        return None;
+    }
+    schema::locate(&source, path)
+}
+
+/// Merges the release's `values:` and `secrets:` files and inline values
+/// in the order helmfile passes them to helm, to find where each key is
+/// set. Also returns the entries that need helmfile to render, which are
+/// skipped here.
+fn merge_files(
+    file: &str,
+    index: usize,
+    rel: &Release,
+    environment: &str,
+) -> Result<(Merged, Vec<String>), String> {
+    let base_dir = Path::new(file).parent().unwrap_or(Path::new(""));
+    let mut templated = Vec::new();
+
+    let mut merged = Merged {
+        values: Value::Mapping(Default::default()),
+        sources: BTreeMap::new(),
+    };
+
+    // helmfile passes values entries in order, then the decrypted secrets
+    let entries = rel
+        .values
+        .iter()
+        .enumerate()
+        .map(|entry| (entry, false))
+        .chain(rel.secrets.iter().enumerate().map(|entry| (entry, true)));
+    for ((i, entry), encrypted) in entries {
+        if entry.is_mapping() && !encrypted {
+            let prefix = format!("releases[{}].values[{}]", index, i);
+            merged.apply(entry, &|key| Source {
+                file: format!("{} (inline)", file),
+                line: locate_in_helmfile(file, &format!("{}.{}", prefix, key)),
+            });
+            continue;
+        }
+
+        let Some(raw) = entry.as_str() else {
+            continue;
+        };
+        let path = match helmfile::substitute(raw, environment, Some(&rel.name)) {
+            Some(path) if !path.ends_with(".gotmpl") => path,
+            Some(path) => {
+                templated.push(path);
+                continue;
+            }
+            None => {
+                templated.push(raw.to_string());
+                continue;
+            }
+        };
+
+        let full_path = base_dir.join(&path);
+        let content = fs::read_to_string(&full_path)
+            .map_err(|e| format!("Failed to read values file {}: {}", full_path.display(), e))?;
+        let mut layer: Value = serde_yaml::from_str(&content)
+            .map_err(|e| format!("Failed to parse values file {}: {}", full_path.display(), e))?;
+        if encrypted {
+            // Keys of sops files are plain text; values are left encrypted
+            if let Some(mapping) = layer.as_mapping_mut() {
+                mapping.remove("sops");
+            }
+            seal(&mut layer);
+        }
+        let display = full_path.display().to_string();
+        merged.apply(&layer, &|key| Source {
+            file: display.clone(),
+            line: schema::locate(&content, key),
+        });
+    }
+
+    Ok((merged, templated))
+}
+
+/// The release's values as helmfile renders them, templates, environment
+/// values and decrypted secrets included, via `helmfile write-values`.
+/// `None` when helmfile is not installed.
+fn render_values(
+    file: &str,
+    environment: Option<&str>,
+    rel: &Release,
+    verbose: bool,
+) -> Result<Option<Value>, String> {
+    if tools::resolve("helmfile").is_none() {
+        return Ok(None);
+    }
+
+    let dir = scratch_dir("values")?;
+    let mut cmd = tools::command("helmfile");
+    cmd.arg("-f").arg(file);
+    if let Some(env) = environment {
+        cmd.arg("-e").arg(env);
+    }
+    cmd.args(["--selector", &format!("name={}", rel.name)])
+        .args(["write-values", "--skip-deps", "--output-file-template"])
+        .arg(dir.join("{{ .Release.Name }}.yaml"));
+    if verbose {
+        print_command(&cmd);
+    }
+
+    let result = cmd
+        .output()
+        .map_err(|e| format!("Failed to execute helmfile: {}", e))
+        .and_then(|output| {
+            if !output.status.success() {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                return Err(format!(
+                    "helmfile write-values failed, showing values from plain files only:\n{}",
+                    redact(stderr.trim())
+                ));
+            }
+            let path = dir.join(format!("{}.yaml", rel.name));
+            let content = fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            match serde_yaml::from_str(&content) {
+                Ok(Value::Null) => Ok(Value::Mapping(Default::default())),
+                Ok(values) => Ok(values),
+                Err(e) => Err(format!("Failed to parse rendered values: {}", e)),
+            }
+        });
+    let _ = fs::remove_dir_all(&dir);
+    result.map(Some)
+}
+
+/// Applies the release's `set:` entries and `--set` arguments, which helm
+/// receives after the values files.
+fn apply_sets(
+    merged: &mut Merged,
+    file: &str,
+    index: usize,
+    rel: &Release,
+    set: &[String],
+) -> Result<(), String> {
+    for (i, entry) in rel.set.iter().enumerate() {
+        let layer = nested(parse_set_key(&entry.name)?, entry.value.clone());
+        let line = locate_in_helmfile(file, &format!("releases[{}].set[{}]", index, i));
+        merged.apply(&layer, &|_| Source {
+            file: format!("{} (set)", file),
+            line,
+        });
+    }
+
+    for argument in set {
+        for (key, value) in parse_set(argument)? {
+            merged.apply(&nested(key, value), &|_| Source {
+                file: "--set".to_string(),
+                line: None,
+            });
+        }
+    }
+    Ok(())
+}
+
+impl Merged {
+    /// Replaces the values with what helmfile rendered. Keys whose value
+    /// the file walk agrees with keep their source; the rest come from
+    /// `templated` entries. Keys from encrypted files stay sealed.
+    fn adopt(&mut self, mut rendered: Value, templated: &[String]) {
+        keep_sealed(&mut rendered, &self.values);
+
+        let walked: BTreeMap<String, &Value> = leaves(&self.values).into_iter().collect();
+        let fallback = Source {
+            file: match templated {
+                [] => "(rendered by helmfile)".to_string(),
+                entries => entries.join(", "),
+            },
+            line: None,
+        };
+        self.sources = leaves(&rendered)
+            .into_iter()
+            .map(|(key, value)| {
+                let source = match (walked.get(&key), self.sources.get(&key)) {
+                    (Some(walked), Some(source)) if *walked == value => source.clone(),
+                    _ => fallback.clone(),
+                };
+                (key, source)
+            })
+            .collect();
+        self.values = rendered;
+    }
+
+    /// Merges `layer` on top, attributing its leaves with `source_of`.
+    fn apply(&mut self, layer: &Value, source_of: &dyn Fn(&str) -> Source) {
+        merge(
+            &mut self.values,
+            layer,
+            &mut Vec::new(),
+            &mut self.sources,
+            source_of,
+        );
+    }
+}
+
+/// Helm's coalescing rules: maps merge key by key, anything else replaces
+/// the previous value, and `null` deletes the key.
+fn merge(
+    target: &mut Value,
+    layer: &Value,
+    path: &mut Vec<Segment>,
+    sources: &mut BTreeMap<String, Source>,
+    source_of: &dyn Fn(&str) -> Source,
+) {
+    let (Some(target), Some(layer)) = (target.as_mapping_mut(), layer.as_mapping()) else {
+        return;
+    };
+
+    for (key, value) in layer {
+        let Some(name) = key_name(key) else {
+            continue;
+        };
+        path.push(Segment::Key(name));
+        let rendered = schema::render_path(path);
+
+        match target.get_mut(key) {
+            _ if value.is_null() => {
+                target.remove(key);
+                forget(sources, &rendered);
+            }
+            Some(existing) if existing.is_mapping() && value.is_mapping() => {
+                merge(existing, value, path, sources, source_of);
+            }
+            _ => {
+                forget(sources, &rendered);
+                target.insert(key.clone(), value.clone());
+                for (leaf, _) in leaves_under(value, path) {
+                    sources.insert(leaf.clone(), source_of(&leaf));
+                }
+            }
+        }
+        path.pop();
+    }
+}
+
+fn key_name(key: &Value) -> Option<String> {
+    match key {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// Drops the sources of `path` and everything below it.
+fn forget(sources: &mut BTreeMap<String, Source>, path: &str) {
+    sources.retain(|key, _| {
+        !(key == path
+            || key.starts_with(&format!("{}.", path))
+            || key.starts_with(&format!("{}[", path)))
+    });
+}
+
+/// Leaf keys of `value` as dotted paths. Lists and empty maps are leaves,
+/// since helm replaces them as a whole.
+fn leaves(value: &Value) -> Vec<(String, &Value)> {
+    leaves_under(value, &mut Vec::new())
+}
+
+fn leaves_under<'a>(value: &'a Value, path: &mut Vec<Segment>) -> Vec<(String, &'a Value)> {
+    match value.as_mapping() {
+        Some(mapping) if !mapping.is_empty() => {
+            let mut out = Vec::new();
+            for (key, child) in mapping {
+                let Some(name) = key_name(key) else {
+                    continue;
+                };
+                path.push(Segment::Key(name));
+                out.extend(leaves_under(child, path));
+                path.pop();
+            }
+            out
+        }
+        _ if path.is_empty() => Vec::new(),
+        _ => vec![(schema::render_path(path), value)],
+    }
+}
+
+/// Copies the sealed markers of `walked` into `rendered`, which holds the
+/// decrypted values.
+fn keep_sealed(rendered: &mut Value, walked: &Value) {
+    let (Some(rendered), Some(walked)) = (rendered.as_mapping_mut(), walked.as_mapping()) else {
+        return;
+    };
+    for (key, child) in rendered.iter_mut() {
+        match walked.get(key) {
+            Some(Value::String(marker)) if marker == SEALED => {
+                *child = Value::String(SEALED.to_string());
+            }
+            Some(walked) => keep_sealed(child, walked),
+            None => {}
+        }
+    }
+}
+
+/// Splits a `--set` argument the way helm does: commas separate
+/// assignments unless escaped as `\,`, `{a,b}` is a list and a backslash
+/// takes the next character literally.
+fn parse_set(argument: &str) -> Result<Vec<(Vec<Segment>, Value)>, String> {
+    let invalid = || format!("Invalid --set value, expected KEY=VALUE: {}", argument);
+    let mut assignments = Vec::new();
+    let mut chars = argument.chars().peekable();
+
+    while chars.peek().is_some() {
+        let (key, stop) = read_until(&mut chars, &['=', ','], true);
+        if stop != Some('=') || key.is_empty() {
+            return Err(invalid());
+        }
+
+        let value = if chars.peek() == Some(&'{') {
+            chars.next();
+            let mut items = Vec::new();
+            loop {
+                let (item, stop) = read_until(&mut chars, &[',', '}'], false);
+                match stop {
+                    Some(',') => items.push(parse_scalar(&item)),
+                    Some('}') => {
+                        if !(item.is_empty() && items.is_empty()) {
+                            items.push(parse_scalar(&item));
+                        }
+                        break;
+                    }
+                    _ => return Err(format!("Unterminated list in --set value: {}", argument)),
+                }
+            }
+            match chars.next() {
+                None | Some(',') => {}
+                Some(_) => return Err(invalid()),
+            }
+            Value::Sequence(items)
+        } else {
+            let (raw, _) = read_until(&mut chars, &[','], false);
+            parse_scalar(&raw)
+        };
+
+        assignments.push((parse_set_key(&key)?, value));
+    }
+
+    if assignments.is_empty() {
+        return Err(invalid());
+    }
+    Ok(assignments)
+}
+
+/// Reads up to the first unescaped character in `stops`, which is
+/// consumed and returned. Escapes are resolved, except `\.` in keys,
+/// which [`parse_set_key`] still needs.
+fn read_until(
+    chars: &mut std::iter::Peekable<std::str::Chars>,
+    stops: &[char],
+    key: bool,
+) -> (String, Option<char>) {
+    let mut text = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('.') if key => text.push_str("\\."),
+                Some(next) => text.push(next),
+                None => text.push('\\'),
+            },
+            c if stops.contains(&c) => return (text, Some(c)),
+            c => text.push(c),
+        }
+    }
+    (text, None)
+}
+
+/// Parses a helm key such as `a.b[0].c`, where `\.` is a literal dot.
+fn parse_set_key(key: &str) -> Result<Vec<Segment>, String> {
+    let invalid = || format!("Invalid --set key: {}", key);
+    let mut segments = Vec::new();
+    let mut name = String::new();
+    let mut chars = key.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'.') => {
+                chars.next();
+                name.push('.');
+            }
+            '.' => {
+                if !name.is_empty() {
+                    segments.push(Segment::Key(std::mem::take(&mut name)));
+                } else if !matches!(segments.last(), Some(Segment::Index(_))) {
+                    return Err(invalid());
+                }
+            }
+            '[' => {
+                if !name.is_empty() {
+                    segments.push(Segment::Key(std::mem::take(&mut name)));
+                }
+                let index: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                segments.push(Segment::Index(index.parse().map_err(|_| invalid())?));
+            }
+            c => name.push(c),
+        }
+    }
+    if !name.is_empty() {
+        segments.push(Segment::Key(name));
+    }
+    if !matches!(segments.first(), Some(Segment::Key(_))) {
+        return Err(invalid());
+    }
+    Ok(segments)
+}
+
+/// Builds `{a: {b: value}}` from the path `a.b`, with indexes creating
+/// lists.
+fn nested(path: Vec<Segment>, value: Value) -> Value {
+    path.into_iter()
+        .rev()
+        .fold(value, |inner, segment| match segment {
+            Segment::Key(name) => {
+                let mut mapping = serde_yaml::Mapping::new();
+                mapping.insert(Value::String(name), inner);
+                Value::Mapping(mapping)
+            }
+            Segment::Index(i) => {
+                let mut items = vec![Value::Null; i];
+                items.push(inner);
+                Value::Sequence(items)
+            }
+        })
+}
+
+/// Parses a `--set` value the way helm does: integers, booleans and
+/// `null` are typed, everything else (floats included) is a string.
+fn parse_scalar(raw: &str) -> Value {
+    match raw {
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        "null" => Value::Null,
+        _ if raw.len() > 1 && raw.starts_with('0') => Value::String(raw.to_string()),
+        _ => raw
+            .parse::<i64>()
+            .map(Value::from)
+            .unwrap_or_else(|_| Value::String(raw.to_string())),
+    }
+}
+
+fn load_baseline(
+    baseline: ValuesBaseline,
+    file: &str,
+    rel: &Release,
+    verbose: bool,
+) -> Result<Value, String> {
+    check_command_available("helm")?;
+
+    let mut cmd = tools::command("helm");
+    match baseline {
+        ValuesBaseline::Defaults => {
+            let chart = if rel.chart.starts_with('.') {
+                let base_dir = Path::new(file).parent().unwrap_or(Path::new(""));
+                base_dir.join(&rel.chart).display().to_string()
+            } else {
+                rel.chart.clone()
+            };
+            cmd.args(["show", "values", &chart]);
+            if let Some(version) = &rel.version {
+                cmd.args(["--version", version]);
+            }
+        }
+        ValuesBaseline::Deployed => {
+            cmd.args(["get", "values", &rel.name, "--output", "yaml"]);
+            if let Some(ns) = &rel.namespace {
+                cmd.args(["--namespace", ns]);
+            }
+        }
+    }
+
+    if verbose {
+        print_command(&cmd);
+    }
+
+    let output = cmd
+        .output()
+        .map_err(|e| format!("Failed to execute helm: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "Failed to load values to compare with:\n{}",
+            stderr
+        ));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if stdout.trim() == "null" || stdout.trim().is_empty() {
+        return Ok(Value::Mapping(Default::default()));
+    }
+    serde_yaml::from_str(&stdout).map_err(|e| format!("Failed to parse helm values: {}", e))
+}
+
+/// Shown instead of values from sops files.
+const SEALED: &str = "(encrypted)";
+
+/// Replaces every leaf with a marker, for values only known encrypted.
+fn seal(value: &mut Value) {
+    match value.as_mapping_mut() {
+        Some(mapping) if !mapping.is_empty() => mapping.values_mut().for_each(seal),
+        _ => *value = Value::String(SEALED.to_string()),
+    }
+}
+
+/// Masks values under sensitive keys, so JSON output stays valid.
+fn mask(value: &mut Value) {
+    let Some(mapping) = value.as_mapping_mut() else {
+        return;
+    };
+    for (key, child) in mapping.iter_mut() {
+        let sensitive = key_name(key).is_some_and(|k| redact::is_sensitive_key(&k));
+        if sensitive && !child.is_null() {
+            *child = Value::String(redact::MASK.to_string());
+        } else {
+            mask(child);
+        }
+    }
+}
+
+fn to_json(value: &Value) -> serde_json::Value {
+    serde_json::to_value(value).unwrap_or(serde_json::Value::Null)
+}
+
+fn print_report(report: &Report, compare: Option<ValuesBaseline>) {
+    if report.entries.is_empty() {
+        println!("  No values set for this release");
+    }
+
+    let width = report
+        .entries
+        .iter()
+        .map(|e| e.key.len() + e.value.to_string().len() + 2)
+        .max()
+        .unwrap_or(0)
+        .min(80);
+
+    for entry in &report.entries {
+        let assignment = redact(&format!("{}: {}", entry.key, entry.value));
+        let mut line = format!(
+            "  {:<width$}  {}",
+            assignment,
+            entry.source.dimmed(),
+            width = width
+        );
+
+        if let (Some(baseline), Some(kind)) = (&entry.baseline, compare) {
+            let label = match kind {
+                ValuesBaseline::Defaults => "default",
+                ValuesBaseline::Deployed => "deployed",
+            };
+            let note = if *baseline == entry.value {
+                format!("(same as {})", label).dimmed()
+            } else if baseline.is_null() {
+                format!("(not {})", label).green()
+            } else {
+                redact(&format!("({}: {})", label, baseline)).yellow()
+            };
+            line = format!("{}  {}", line, note);
+        }
+        println!("{}", line);
+    }
+
+    if compare == Some(ValuesBaseline::Deployed) && !report.baseline_only.is_empty() {
+        println!();
+        println!("{}", "Deployed but no longer set:".yellow().bold());
+        for entry in &report.baseline_only {
+            let value = entry.baseline.clone().unwrap_or_default();
+            println!("  {}", redact(&format!("{}: {}", entry.key, value)).red());
+        }
+    }
+}
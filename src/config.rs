@@ -1,34 +1,481 @@
+use crate::ci::esay;
+use crate::schema::{self, Issue, Segment};
 use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::env;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Once;
 
 #[derive(Serialize, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
 pub struct Config {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub default_environment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub default_concurrency: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub default_timeout: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub auto_update_repos: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub preferred_context: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub log_level: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_namespace: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_skip_deps: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_strict: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_wait: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_detailed: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub repositories: Option<Vec<Repository>>,
+    /// Extra key patterns (`*` wildcards) whose values are masked in output
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redact_patterns: Option<Vec<String>>,
+    /// Named combinations of environment, context and namespace
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profiles: Option<BTreeMap<String, Profile>>,
+    /// Executable locations overriding the `PATH` lookup, keyed by tool
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<BTreeMap<String, String>>,
+    /// Install sources for helm plugins: a local archive, directory or URL
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plugins: Option<BTreeMap<String, String>>,
+    /// Short names for kubeconfig contexts, e.g. `prod-eu: arn:aws:eks:...`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context_aliases: Option<BTreeMap<String, String>>,
+    /// Markdown job summary file for CI mode, instead of `$GITHUB_STEP_SUMMARY`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ci_summary_file: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub environment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Repository {
     pub name: String,
     pub url: String,
 }
 
+/// System-wide configuration, the lowest-precedence layer.
+pub const SYSTEM_CONFIG_PATH: &str = "/etc/helmctl/config.yaml";
+
+/// File name looked up from the current directory upwards.
+pub const PROJECT_CONFIG_NAME: &str = "helmctl.yaml";
+
+/// Prefix of environment variables that override configuration keys.
+/// Nested keys are separated by a double underscore.
+pub const ENV_PREFIX: &str = "HELMCTL_";
+
+/// A configuration source, in increasing order of precedence.
+pub struct ConfigLayer {
+    pub name: &'static str,
+    pub path: PathBuf,
+}
+
+/// The effective configuration merged from all layers, with the source of
+/// every key that was set.
+pub struct ResolvedConfig {
+    pub config: Config,
+    pub origins: BTreeMap<String, String>,
+}
+
 impl Config {
     pub fn load(path: &str) -> Result<Config, String> {
+        match read_layer(Path::new(path))? {
+            Some(value) => serde_yaml::from_value(value)
+                .map_err(|e| format!("Failed to parse config file: {}", e)),
+            None => Ok(Config::default()),
+        }
+    }
+
+    /// Config files consulted for `explicit` (the `--config` flag): the
+    /// system file, the user file and the project file. An explicit path
+    /// replaces the search for the nearest `helmctl.yaml`.
+    pub fn layers(explicit: Option<&str>) -> Vec<ConfigLayer> {
+        let mut layers = vec![ConfigLayer {
+            name: "system",
+            path: PathBuf::from(SYSTEM_CONFIG_PATH),
+        }];
+
+        if let Some(path) = user_config_path() {
+            layers.push(ConfigLayer { name: "user", path });
+        }
+
+        layers.push(ConfigLayer {
+            name: "project",
+            path: Config::project_path(explicit),
+        });
+
+        layers
+    }
+
+    /// The file written by `config init` and `config set`: the explicit
+    /// path, else the nearest `helmctl.yaml` above the current directory,
+    /// else `./helmctl.yaml`.
+    pub fn project_path(explicit: Option<&str>) -> PathBuf {
+        if let Some(path) = explicit {
+            return PathBuf::from(path);
+        }
+
+        env::current_dir()
+            .ok()
+            .and_then(|cwd| {
+                cwd.ancestors()
+                    .map(|dir| dir.join(PROJECT_CONFIG_NAME))
+                    .find(|candidate| candidate.is_file())
+            })
+            .unwrap_or_else(|| PathBuf::from(PROJECT_CONFIG_NAME))
+    }
+
+    /// Merges the system, user and project files and `HELMCTL_*`
+    /// environment variables, later layers overriding earlier ones.
+    pub fn resolve(explicit: Option<&str>) -> Result<ResolvedConfig, String> {
+        let mut merged = serde_yaml::Value::Mapping(Default::default());
+        let mut origins = BTreeMap::new();
+
+        for layer in Config::layers(explicit) {
+            if let Some(value) = read_layer(&layer.path)? {
+                let source = format!("{} ({})", layer.name, layer.path.display());
+                merge(&mut merged, value, "", &mut |key| {
+                    origins.insert(key, source.clone());
+                });
+            }
+        }
+
+        let (env_value, env_origins) = env_layer()?;
+        merge(&mut merged, env_value, "", &mut |_| {});
+        origins.extend(env_origins);
+
+        let config = serde_yaml::from_value(merged)
+            .map_err(|e| format!("Failed to parse config file: {}", e))?;
+
+        Ok(ResolvedConfig { config, origins })
+    }
+
+    /// Checks a config file against [`Config::schema`] without loading it,
+    /// returning every issue found. A missing file has no issues.
+    pub fn validate_file(path: &str) -> Result<Vec<Issue>, String> {
         if !Path::new(path).exists() {
-            return Ok(Config::default());
+            return Ok(Vec::new());
         }
 
         let content =
             fs::read_to_string(path).map_err(|e| format!("Failed to read config file: {}", e))?;
 
-        serde_yaml::from_str(&content).map_err(|e| format!("Failed to parse config file: {}", e))
+        let value: serde_yaml::Value = match serde_yaml::from_str(&content) {
+            Ok(value) => value,
+            Err(e) => {
+                return Ok(vec![Issue {
+                    path: "(root)".to_string(),
+                    line: e.location().map(|l| l.line()),
+                    message: format!("YAML syntax error: {}", e),
+                }])
+            }
+        };
+
+        Ok(schema::validate(&value, &Config::schema(), &content))
+    }
+
+    /// JSON Schema describing helmctl.yaml, used for validation and editor
+    /// integration via `helmctl config schema`.
+    pub fn schema() -> serde_json::Value {
+        json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "title": "helmctl configuration",
+            "type": "object",
+            "additionalProperties": false,
+            "properties": {
+                "default_environment": {
+                    "description": "Default environment for operations",
+                    "type": "string"
+                },
+                "default_concurrency": {
+                    "description": "Default concurrency level for deployments",
+                    "type": "integer",
+                    "minimum": 1,
+                    "maximum": 255
+                },
+                "default_timeout": {
+                    "description": "Default timeout for operations (seconds)",
+                    "type": "integer",
+                    "minimum": 1,
+                    "maximum": u32::MAX
+                },
+                "auto_update_repos": {
+                    "description": "Automatically update Helm repositories",
+                    "type": "boolean"
+                },
+                "preferred_context": {
+                    "description": "Default Kubernetes context",
+                    "type": "string"
+                },
+                "log_level": {
+                    "description": "Logging level",
+                    "type": "string",
+                    "enum": ["debug", "info", "warn", "error"]
+                },
+                "default_namespace": {
+                    "description": "Default namespace for k8s-deploy",
+                    "type": "string"
+                },
+                "default_skip_deps": {
+                    "description": "Skip dependency updates on deploy",
+                    "type": "boolean"
+                },
+                "default_strict": {
+                    "description": "Fail lint on warnings",
+                    "type": "boolean"
+                },
+                "default_wait": {
+                    "description": "Wait for rollouts after k8s-deploy",
+                    "type": "boolean"
+                },
+                "default_detailed": {
+                    "description": "Show detailed status",
+                    "type": "boolean"
+                },
+                "repositories": {
+                    "description": "Helm chart repositories",
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "additionalProperties": false,
+                        "required": ["name", "url"],
+                        "properties": {
+                            "name": { "type": "string" },
+                            "url": { "type": "string", "format": "uri" }
+                        }
+                    }
+                },
+                "redact_patterns": {
+                    "description": "Extra key patterns (`*` wildcards) whose values are masked in output",
+                    "type": "array",
+                    "items": { "type": "string" }
+                },
+                "tools": {
+                    "description": "Executable locations overriding the PATH lookup",
+                    "type": "object",
+                    "additionalProperties": false,
+                    "properties": {
+                        "gpg": { "type": "string" },
+                        "helm": { "type": "string" },
+                        "helmfile": { "type": "string" },
+                        "kubectl": { "type": "string" },
+                        "kustomize": { "type": "string" },
+                        "sops": { "type": "string" }
+                    }
+                },
+                "plugins": {
+                    "description": "Install sources for helm plugins: a local .tgz archive, directory or URL",
+                    "type": "object",
+                    "additionalProperties": { "type": "string" }
+                },
+                "context_aliases": {
+                    "description": "Short names for kubeconfig contexts",
+                    "type": "object",
+                    "additionalProperties": { "type": "string" }
+                },
+                "ci_summary_file": {
+                    "description": "Markdown job summary file for CI mode, instead of $GITHUB_STEP_SUMMARY",
+                    "type": "string"
+                },
+                "profiles": {
+                    "description": "Named combinations of environment, context and namespace",
+                    "type": "object",
+                    "additionalProperties": {
+                        "type": "object",
+                        "additionalProperties": false,
+                        "properties": {
+                            "environment": { "type": "string" },
+                            "context": { "type": "string" },
+                            "namespace": { "type": "string" }
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    /// The profile named `name`, or an empty one when no profile is
+    /// selected.
+    pub fn profile(&self, name: Option<&str>) -> Result<Profile, String> {
+        let Some(name) = name else {
+            return Ok(Profile::default());
+        };
+        let profiles = self.profiles.as_ref();
+        if let Some(profile) = profiles.and_then(|p| p.get(name)) {
+            return Ok(profile.clone());
+        }
+
+        let available: Vec<&str> = profiles
+            .into_iter()
+            .flat_map(|p| p.keys().map(String::as_str))
+            .collect();
+        Err(if available.is_empty() {
+            format!("Unknown profile: {} (no profiles are configured)", name)
+        } else {
+            format!(
+                "Unknown profile: {} (available: {})",
+                name,
+                available.join(", ")
+            )
+        })
+    }
+
+    /// Value at a dotted path such as `repositories[1].url`; null when the
+    /// key is valid but not set.
+    pub fn get_value(&self, key: &str) -> Result<serde_yaml::Value, String> {
+        let path = parse_key(key)?;
+        let mut current = self.to_value()?;
+
+        for segment in &path {
+            let child = match segment {
+                Segment::Key(name) => current.get(name.as_str()),
+                Segment::Index(index) => current.get(*index),
+            };
+            match child {
+                Some(child) => current = child.clone(),
+                None => return Ok(serde_yaml::Value::Null),
+            }
+        }
+
+        Ok(current)
+    }
+
+    /// Sets the value at a dotted path. `raw` is read as YAML, e.g. `300`
+    /// or `[a, b]`, falling back to the plain text, and the first reading
+    /// the [`Config`] struct accepts at that path wins. Missing parents are
+    /// created and an index one past the end appends to a list.
+    pub fn set_value(&mut self, key: &str, raw: &str) -> Result<(), String> {
+        let path = parse_key(key)?;
+        let root = self.to_value()?;
+
+        let readings = [
+            serde_yaml::from_str(raw)
+                .ok()
+                .filter(|v: &serde_yaml::Value| !v.is_null()),
+            Some(serde_yaml::Value::String(raw.to_string())),
+        ];
+        let mut first_error = None;
+        for reading in readings.into_iter().flatten() {
+            let mut document = root.clone();
+            *slot_mut(&mut document, &path, key)? = reading;
+            match serde_yaml::from_value::<Config>(document.clone()) {
+                Ok(_) => return self.replace_with(document, key),
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+
+        let error = first_error.expect("the plain text is always tried");
+        Err(format!("Invalid value for {}: {}", key, error))
+    }
+
+    /// Checks the configuration against [`Config::schema`], for values set
+    /// without going through [`Config::set_value`].
+    pub fn validate(&self) -> Result<(), String> {
+        match schema::validate(&self.to_value()?, &Config::schema(), "").first() {
+            Some(issue) => Err(format!("Invalid configuration: {}", issue)),
+            None => Ok(()),
+        }
+    }
+
+    /// Removes the value at a dotted path. List items are removed; other
+    /// keys revert to unset.
+    pub fn unset_value(&mut self, key: &str) -> Result<(), String> {
+        let path = parse_key(key)?;
+        let mut root = self.to_value()?;
+
+        let (last, parents) = path.split_last().expect("parse_key rejects empty keys");
+        let mut parent = &mut root;
+        for segment in parents {
+            let child = match segment {
+                Segment::Key(name) => parent.get_mut(name.as_str()),
+                Segment::Index(index) => parent.get_mut(*index),
+            };
+            match child {
+                Some(child) => parent = child,
+                None => return Ok(()),
+            }
+        }
+
+        match (last, parent) {
+            (Segment::Key(name), serde_yaml::Value::Mapping(mapping)) => {
+                mapping.remove(name.as_str());
+            }
+            (Segment::Index(index), serde_yaml::Value::Sequence(items)) if *index < items.len() => {
+                items.remove(*index);
+            }
+            _ => {}
+        }
+
+        self.replace_with(root, key)
+    }
+
+    fn to_value(&self) -> Result<serde_yaml::Value, String> {
+        serde_yaml::to_value(self).map_err(|e| format!("Failed to serialize config: {}", e))
+    }
+
+    /// Validates an edited document and deserializes it into `self`.
+    fn replace_with(&mut self, value: serde_yaml::Value, key: &str) -> Result<(), String> {
+        if let Some(issue) = schema::validate(&value, &Config::schema(), "").first() {
+            return Err(if issue.path == key {
+                format!("Invalid value for {}: {}", key, issue.message)
+            } else {
+                format!("Invalid value for {}: {}", key, issue)
+            });
+        }
+
+        *self = serde_yaml::from_value(value)
+            .map_err(|e| format!("Invalid value for {}: {}", key, e))?;
+        Ok(())
+    }
+
+    /// Sets (or with `None` removes) one top-level key of the config file
+    /// at `path`, leaving the other keys as they are.
+    pub fn update_key(
+        path: &str,
+        key: &str,
+        value: Option<serde_yaml::Value>,
+    ) -> Result<(), String> {
+        let mut document = read_layer(Path::new(path))?
+            .unwrap_or_else(|| serde_yaml::Value::Mapping(Default::default()));
+        let mapping = document
+            .as_mapping_mut()
+            .ok_or_else(|| format!("Invalid config file {}: not a mapping", path))?;
+        match value {
+            Some(value) => {
+                mapping.insert(key.into(), value);
+            }
+            None => {
+                mapping.remove(key);
+            }
+        }
+
+        if let Some(issue) = schema::validate(&document, &Config::schema(), "").first() {
+            return Err(format!("Invalid value for {}", issue));
+        }
+        let content = serde_yaml::to_string(&document)
+            .map_err(|e| format!("Failed to serialize config: {}", e))?;
+        fs::write(path, content).map_err(|e| format!("Failed to write config file: {}", e))
     }
 
     pub fn save(&self, path: &str) -> Result<(), String> {
@@ -46,6 +493,11 @@ impl Config {
             auto_update_repos: Some(true),
             preferred_context: None,
             log_level: Some("info".to_string()),
+            default_namespace: None,
+            default_skip_deps: None,
+            default_strict: None,
+            default_wait: None,
+            default_detailed: None,
             repositories: Some(vec![
                 Repository {
                     name: "bitnami".to_string(),
@@ -56,6 +508,249 @@ impl Config {
                     url: "https://charts.helm.sh/stable".to_string(),
                 },
             ]),
+            redact_patterns: None,
+            profiles: None,
+            tools: None,
+            plugins: None,
+            context_aliases: None,
+            ci_summary_file: None,
+        }
+    }
+}
+
+/// Parses a dotted key and checks that the schema knows it.
+fn parse_key(key: &str) -> Result<Vec<Segment>, String> {
+    let path = schema::parse_path(key);
+    if path.is_empty() || schema::render_path(&path) != key {
+        return Err(format!("Invalid configuration key: {}", key));
+    }
+
+    let root = Config::schema();
+    for depth in 1..=path.len() {
+        if schema::subschema(&root, &path[..depth]).is_none() {
+            let parent = schema::subschema(&root, &path[..depth - 1]);
+            let known: Vec<&str> = parent
+                .and_then(|s| s["properties"].as_object())
+                .map(|p| p.keys().map(String::as_str).collect())
+                .unwrap_or_default();
+            return Err(format!(
+                "Unknown configuration key: {}. Available keys: {}",
+                key,
+                known.join(", ")
+            ));
+        }
+    }
+
+    Ok(path)
+}
+
+/// Walks to the value at `path`, creating missing mappings along the way.
+fn slot_mut<'a>(
+    root: &'a mut serde_yaml::Value,
+    path: &[Segment],
+    key: &str,
+) -> Result<&'a mut serde_yaml::Value, String> {
+    let mut current = root;
+    for segment in path {
+        current = match segment {
+            Segment::Key(name) => {
+                if current.is_null() {
+                    *current = serde_yaml::Value::Mapping(Default::default());
+                }
+                current
+                    .as_mapping_mut()
+                    .ok_or_else(|| format!("Cannot set {}: parent is not a mapping", key))?
+                    .entry(serde_yaml::Value::String(name.clone()))
+                    .or_insert(serde_yaml::Value::Null)
+            }
+            Segment::Index(index) => {
+                if current.is_null() {
+                    *current = serde_yaml::Value::Sequence(Vec::new());
+                }
+                let items = current
+                    .as_sequence_mut()
+                    .ok_or_else(|| format!("Cannot set {}: parent is not a list", key))?;
+                if *index == items.len() {
+                    items.push(serde_yaml::Value::Mapping(Default::default()));
+                }
+                let len = items.len();
+                items.get_mut(*index).ok_or_else(|| {
+                    format!(
+                        "Cannot set {}: index {} is out of range (list has {} item(s))",
+                        key, index, len
+                    )
+                })?
+            }
+        };
+    }
+    Ok(current)
+}
+
+/// Reads and validates one config file. Missing and empty files yield
+/// `None`.
+fn read_layer(path: &Path) -> Result<Option<serde_yaml::Value>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read config file: {}", e))?;
+
+    let value: serde_yaml::Value = serde_yaml::from_str(&content)
+        .map_err(|e| format!("Failed to parse config file: {}", e))?;
+    if value.is_null() {
+        return Ok(None);
+    }
+
+    let issues = schema::validate(&value, &Config::schema(), &content);
+    if !issues.is_empty() {
+        let details: Vec<String> = issues.iter().map(|i| format!("  {}", i)).collect();
+        return Err(format!(
+            "Invalid config file {}:\n{}",
+            path.display(),
+            details.join("\n")
+        ));
+    }
+
+    Ok(Some(value))
+}
+
+fn user_config_path() -> Option<PathBuf> {
+    let base = match env::var("XDG_CONFIG_HOME") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => Path::new(&env::var("HOME").ok()?).join(".config"),
+    };
+    Some(base.join("helmctl").join("config.yaml"))
+}
+
+/// Builds a config layer from `HELMCTL_*` variables, e.g.
+/// `HELMCTL_DEFAULT_TIMEOUT=600`. Values are parsed as YAML scalars.
+/// Variables that do not name a top-level key are ignored with a warning,
+/// since other tools may share the prefix.
+fn env_layer() -> Result<(serde_yaml::Value, BTreeMap<String, String>), String> {
+    let mut value = serde_yaml::Value::Mapping(Default::default());
+    let mut origins = BTreeMap::new();
+    // Variable that set each dotted path, to name both sides of a conflict
+    let mut setters: BTreeMap<String, String> = BTreeMap::new();
+
+    let schema = Config::schema();
+    let known = &schema["properties"];
+
+    let mut vars: Vec<(String, String)> = env::vars()
+        .filter(|(name, _)| name.starts_with(ENV_PREFIX))
+        .collect();
+    vars.sort();
+
+    // The configuration is resolved more than once per run, e.g. by main
+    // and again by `config validate`; warn about unknown variables once
+    static WARN_UNKNOWN: Once = Once::new();
+    WARN_UNKNOWN.call_once(|| {
+        for (name, _) in &vars {
+            let key = name[ENV_PREFIX.len()..]
+                .split("__")
+                .next()
+                .unwrap_or_default()
+                .to_lowercase();
+            if known.get(&key).is_none() {
+                esay!("⚠️  Ignoring {}: {} is not a configuration key", name, key);
+            }
+        }
+    });
+
+    for (name, raw) in vars {
+        let path: Vec<String> = name[ENV_PREFIX.len()..]
+            .split("__")
+            .map(str::to_lowercase)
+            .collect();
+        if known.get(&path[0]).is_none() {
+            continue;
+        }
+
+        let parsed = serde_yaml::from_str(&raw)
+            .ok()
+            .filter(|v: &serde_yaml::Value| !v.is_mapping() && !v.is_sequence())
+            .unwrap_or(serde_yaml::Value::String(raw.clone()));
+
+        let conflict = |other: &str| {
+            format!(
+                "Conflicting environment variables {} and {}: one sets a value where the other expects nested keys",
+                other, name
+            )
+        };
+
+        let mut cursor = &mut value;
+        for (depth, segment) in path.iter().enumerate() {
+            let Some(mapping) = cursor.as_mapping_mut() else {
+                return Err(conflict(&setters[&path[..depth].join(".")]));
+            };
+            cursor = mapping
+                .entry(serde_yaml::Value::String(segment.clone()))
+                .or_insert_with(|| serde_yaml::Value::Mapping(Default::default()));
+        }
+
+        let key = path.join(".");
+        let nested = format!("{}.", key);
+        if let Some((_, other)) = setters.iter().find(|(path, _)| path.starts_with(&nested)) {
+            return Err(conflict(other));
+        }
+        *cursor = parsed;
+
+        origins.insert(key.clone(), format!("env ({})", name));
+        setters.insert(key, name);
+    }
+
+    let issues = schema::validate(&value, &schema, "");
+    if !issues.is_empty() {
+        let details: Vec<String> = issues
+            .iter()
+            .map(|i| {
+                let var = origins
+                    .get(&i.path)
+                    .map_or(String::new(), |o| format!(" [{}]", o));
+                format!("  {}: {}{}", i.path, i.message, var)
+            })
+            .collect();
+        return Err(format!(
+            "Invalid configuration in environment variables:\n{}",
+            details.join("\n")
+        ));
+    }
+
+    Ok((value, origins))
+}
+
+/// Deep-merges `overlay` into `base`. Mappings merge key by key; any other
+/// value replaces what was there. `record` receives the dotted path of each
+/// value taken from `overlay`.
+fn merge(
+    base: &mut serde_yaml::Value,
+    overlay: serde_yaml::Value,
+    prefix: &str,
+    record: &mut dyn FnMut(String),
+) {
+    match (base, overlay) {
+        (serde_yaml::Value::Mapping(base), serde_yaml::Value::Mapping(overlay)) => {
+            for (key, value) in overlay {
+                let name = key.as_str().unwrap_or_default();
+                let path = if prefix.is_empty() {
+                    name.to_string()
+                } else {
+                    format!("{}.{}", prefix, name)
+                };
+
+                match base.get_mut(&key) {
+                    Some(existing) if existing.is_mapping() && value.is_mapping() => {
+                        merge(existing, value, &path, record);
+                    }
+                    _ => {
+                        if !value.is_null() {
+                            record(path);
+                            base.insert(key, value);
+                        }
+                    }
+                }
+            }
         }
+        (base, overlay) => *base = overlay,
     }
 }
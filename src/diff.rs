@@ -0,0 +1,246 @@
+//! Line-based unified diffs, for comparing rendered manifests without an
+//! external diff tool.
+
+/// Lines of context shown around each change.
+const CONTEXT: usize = 3;
+
+enum Op<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// Unified diff of `old` and `new`, headed by their labels. Empty when the
+/// texts are equal.
+pub fn unified(old: &str, new: &str, old_label: &str, new_label: &str) -> Vec<String> {
+    let ops = operations(
+        &old.lines().collect::<Vec<_>>(),
+        &new.lines().collect::<Vec<_>>(),
+    );
+    if ops.iter().all(|op| matches!(op, Op::Same(_))) {
+        return Vec::new();
+    }
+
+    let mut lines = vec![format!("--- {}", old_label), format!("+++ {}", new_label)];
+
+    // Positions in `ops` of changed lines, grown into hunks with context
+    let changed: Vec<usize> = ops
+        .iter()
+        .enumerate()
+        .filter(|(_, op)| !matches!(op, Op::Same(_)))
+        .map(|(i, _)| i)
+        .collect();
+
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for &i in &changed {
+        let start = i.saturating_sub(CONTEXT);
+        let end = (i + CONTEXT + 1).min(ops.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    // Line numbers at `counted` in `ops` on either side, advanced hunk by hunk
+    let (mut counted, mut old_next, mut new_next) = (0, 1, 1);
+    for (start, end) in hunks {
+        for op in &ops[counted..start] {
+            match op {
+                Op::Same(_) => {
+                    old_next += 1;
+                    new_next += 1;
+                }
+                Op::Removed(_) => old_next += 1,
+                Op::Added(_) => new_next += 1,
+            }
+        }
+        counted = start;
+        let (mut old_line, mut new_line) = (old_next, new_next);
+        let hunk = &ops[start..end];
+        let old_count = hunk.iter().filter(|op| !matches!(op, Op::Added(_))).count();
+        let new_count = hunk
+            .iter()
+            .filter(|op| !matches!(op, Op::Removed(_)))
+            .count();
+
+        // An empty range starts at the line before it
+        if old_count == 0 {
+            old_line -= 1;
+        }
+        if new_count == 0 {
+            new_line -= 1;
+        }
+        lines.push(format!(
+            "@@ -{},{} +{},{} @@",
+            old_line, old_count, new_line, new_count
+        ));
+        for op in hunk {
+            lines.push(match op {
+                Op::Same(line) => format!(" {}", line),
+                Op::Removed(line) => format!("-{}", line),
+                Op::Added(line) => format!("+{}", line),
+            });
+        }
+    }
+
+    lines
+}
+
+/// Shortest edit script between the lines of `old` and `new`, using Myers'
+/// algorithm in linear space so large manifests don't need a quadratic table.
+fn operations<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Op<'a>> {
+    let mut ops = Vec::with_capacity(old.len().max(new.len()));
+    diff_into(old, new, &mut ops);
+    ops
+}
+
+fn diff_into<'a>(old: &[&'a str], new: &[&'a str], ops: &mut Vec<Op<'a>>) {
+    // Common prefix and suffix never take part in the edit
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let (old_rest, new_rest) = (&old[prefix..], &new[prefix..]);
+    let suffix = old_rest
+        .iter()
+        .rev()
+        .zip(new_rest.iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old_rest[..old_rest.len() - suffix];
+    let new_mid = &new_rest[..new_rest.len() - suffix];
+
+    ops.extend(old[..prefix].iter().map(|line| Op::Same(line)));
+    if old_mid.is_empty() {
+        ops.extend(new_mid.iter().map(|line| Op::Added(line)));
+    } else if new_mid.is_empty() {
+        ops.extend(old_mid.iter().map(|line| Op::Removed(line)));
+    } else {
+        match middle_snake(old_mid, new_mid) {
+            Some((x, y)) => {
+                diff_into(&old_mid[..x], &new_mid[..y], ops);
+                diff_into(&old_mid[x..], &new_mid[y..], ops);
+            }
+            None => {
+                ops.extend(old_mid.iter().map(|line| Op::Removed(line)));
+                ops.extend(new_mid.iter().map(|line| Op::Added(line)));
+            }
+        }
+    }
+    ops.extend(
+        old_rest[old_rest.len() - suffix..]
+            .iter()
+            .map(|line| Op::Same(line)),
+    );
+}
+
+/// Point where the forward and reverse searches for the shortest edit path
+/// meet, splitting the problem in two. Both inputs must be non-empty.
+fn middle_snake(old: &[&str], new: &[&str]) -> Option<(usize, usize)> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let max_d = (n + m + 1) / 2;
+    let offset = max_d + 1;
+    let width = (2 * max_d + 3) as usize;
+    // Furthest x reached on each diagonal k = x - y, from the start and from the end
+    let mut forward = vec![-1isize; width];
+    let mut reverse = vec![-1isize; width];
+    forward[(offset + 1) as usize] = 0;
+    reverse[(offset + 1) as usize] = 0;
+
+    let delta = n - m;
+    let odd = delta % 2 != 0;
+    // Diagonals that ran off the edges are trimmed from later rounds
+    let (mut k1_start, mut k1_end, mut k2_start, mut k2_end) = (0, 0, 0, 0);
+
+    for d in 0..max_d {
+        let mut k1 = -d + k1_start;
+        while k1 <= d - k1_end {
+            let i = (offset + k1) as usize;
+            let mut x = if k1 == -d || (k1 != d && forward[i - 1] < forward[i + 1]) {
+                forward[i + 1]
+            } else {
+                forward[i - 1] + 1
+            };
+            let mut y = x - k1;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[i] = x;
+            if x > n {
+                k1_end += 2;
+            } else if y > m {
+                k1_start += 2;
+            } else if odd {
+                let j = offset + delta - k1;
+                if (0..width as isize).contains(&j)
+                    && reverse[j as usize] != -1
+                    && x >= n - reverse[j as usize]
+                {
+                    return Some((x as usize, y as usize));
+                }
+            }
+            k1 += 2;
+        }
+
+        let mut k2 = -d + k2_start;
+        while k2 <= d - k2_end {
+            let i = (offset + k2) as usize;
+            let mut x = if k2 == -d || (k2 != d && reverse[i - 1] < reverse[i + 1]) {
+                reverse[i + 1]
+            } else {
+                reverse[i - 1] + 1
+            };
+            let mut y = x - k2;
+            while x < n && y < m && old[(n - x - 1) as usize] == new[(m - y - 1) as usize] {
+                x += 1;
+                y += 1;
+            }
+            reverse[i] = x;
+            if x > n {
+                k2_end += 2;
+            } else if y > m {
+                k2_start += 2;
+            } else if !odd {
+                let j = offset + delta - k2;
+                if (0..width as isize).contains(&j) && forward[j as usize] != -1 {
+                    let fx = forward[j as usize];
+                    let fy = fx - (j - offset);
+                    if fx >= n - x {
+                        return Some((fx as usize, fy as usize));
+                    }
+                }
+            }
+            k2 += 2;
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_handles_large_manifests() {
+        // Far beyond what a quadratic table would fit in memory
+        let old: Vec<String> = (0..60_000).map(|i| format!("line: {}", i)).collect();
+        let mut new = old.clone();
+        new[10] = "line: changed".to_string();
+        new.remove(30_000);
+        new.insert(59_000, "line: added".to_string());
+
+        let diff = unified(&old.join("\n"), &new.join("\n"), "old", "new");
+        let hunks: Vec<&String> = diff.iter().filter(|l| l.starts_with("@@")).collect();
+        assert_eq!(
+            hunks,
+            [
+                "@@ -8,7 +8,7 @@",
+                "@@ -29998,7 +29998,6 @@",
+                "@@ -58999,6 +58998,7 @@"
+            ]
+        );
+        assert!(diff.contains(&"-line: 10".to_string()));
+        assert!(diff.contains(&"+line: changed".to_string()));
+        assert!(diff.contains(&"-line: 30000".to_string()));
+        assert!(diff.contains(&"+line: added".to_string()));
+        assert_eq!(diff.len(), 2 + 3 + 8 + 7 + 7);
+    }
+}
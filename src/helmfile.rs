@@ -0,0 +1,219 @@
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+use std::fs;
+
+/// The subset of a helmfile that helmctl inspects directly, without
+/// shelling out to helmfile. Multi-document helmfiles are merged.
+#[derive(Deserialize, Default)]
+pub struct Helmfile {
+    #[serde(default)]
+    pub repositories: Vec<HelmfileRepository>,
+    #[serde(default)]
+    pub environments: BTreeMap<String, Environment>,
+    #[serde(default)]
+    pub releases: Vec<Release>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct Environment {
+    #[serde(default)]
+    pub values: Vec<serde_yaml::Value>,
+    #[serde(default)]
+    pub secrets: Vec<serde_yaml::Value>,
+}
+
+#[derive(Deserialize)]
+pub struct HelmfileRepository {
+    pub name: String,
+    #[serde(default)]
+    pub oci: bool,
+}
+
+#[derive(Deserialize)]
+pub struct Release {
+    pub name: String,
+    #[serde(default)]
+    pub namespace: Option<String>,
+    pub chart: String,
+    #[serde(default, deserialize_with = "string_or_number")]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub values: Vec<serde_yaml::Value>,
+    #[serde(default)]
+    pub secrets: Vec<serde_yaml::Value>,
+    #[serde(default)]
+    pub set: Vec<SetValue>,
+}
+
+/// A `set:` entry of a release, applied after its values files.
+#[derive(Deserialize)]
+pub struct SetValue {
+    pub name: String,
+    #[serde(default)]
+    pub value: serde_yaml::Value,
+}
+
+/// A values or secrets file referenced from the helmfile.
+pub struct FileReference {
+    pub path: String,
+    /// Listed under `secrets:` rather than `values:`
+    pub secret: bool,
+    /// Owning release, `None` for environment values
+    pub release: Option<String>,
+}
+
+impl Release {
+    /// Repository name and chart name for `repo/chart` references, `None`
+    /// for local paths and OCI references.
+    pub fn repo_chart(&self) -> Option<(&str, &str)> {
+        if self.chart.starts_with('.') || self.chart.starts_with('/') || self.chart.contains("://")
+        {
+            return None;
+        }
+        self.chart.split_once('/')
+    }
+
+    /// The chart name without its repository prefix.
+    pub fn chart_name(&self) -> &str {
+        self.chart
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .unwrap_or(&self.chart)
+    }
+}
+
+impl Helmfile {
+    pub fn load(path: &str) -> Result<Helmfile, String> {
+        let content =
+            fs::read_to_string(path).map_err(|e| format!("Failed to read helmfile: {}", e))?;
+        Helmfile::parse(&content)
+    }
+
+    pub fn parse(content: &str) -> Result<Helmfile, String> {
+        // Lines that consist solely of Go template actions ({{ if ... }},
+        // {{ end }}) cannot be parsed as YAML; inline expressions can.
+        let stripped: String = content
+            .lines()
+            .filter(|line| {
+                let t = line.trim();
+                !(t.starts_with("{{") && t.ends_with("}}"))
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let mut helmfile = Helmfile::default();
+        for document in serde_yaml::Deserializer::from_str(&stripped) {
+            let value = serde_yaml::Value::deserialize(document)
+                .map_err(|e| format!("Failed to parse helmfile: {}", e))?;
+            if value.is_null() {
+                continue;
+            }
+            let part: Helmfile = serde_yaml::from_value(value)
+                .map_err(|e| format!("Failed to parse helmfile: {}", e))?;
+            helmfile.repositories.extend(part.repositories);
+            helmfile.environments.extend(part.environments);
+            helmfile.releases.extend(part.releases);
+        }
+
+        Ok(helmfile)
+    }
+
+    /// Whether `repo` is declared in the helmfile as an OCI registry.
+    pub fn is_oci_repository(&self, repo: &str) -> bool {
+        self.repositories.iter().any(|r| r.name == repo && r.oci)
+    }
+
+    /// Values and secrets files referenced by the selected environment and
+    /// releases. `{{ .Environment.Name }}` is substituted; paths that still
+    /// contain template expressions are skipped.
+    pub fn file_references(
+        &self,
+        environment: Option<&str>,
+        release: Option<&str>,
+    ) -> Vec<FileReference> {
+        let env_name = environment.unwrap_or("default");
+        let mut references = Vec::new();
+
+        if let Some(env) = self.environments.get(env_name) {
+            push_references(&mut references, &env.values, false, None, env_name);
+            push_references(&mut references, &env.secrets, true, None, env_name);
+        }
+
+        for rel in self.select_releases(release) {
+            push_references(
+                &mut references,
+                &rel.values,
+                false,
+                Some(&rel.name),
+                env_name,
+            );
+            push_references(
+                &mut references,
+                &rel.secrets,
+                true,
+                Some(&rel.name),
+                env_name,
+            );
+        }
+
+        references
+    }
+
+    /// Releases matching an optional `--release` selector.
+    pub fn select_releases(&self, release: Option<&str>) -> Vec<&Release> {
+        self.releases
+            .iter()
+            .filter(|r| release.is_none_or(|name| r.name == name))
+            .collect()
+    }
+}
+
+fn push_references(
+    references: &mut Vec<FileReference>,
+    entries: &[serde_yaml::Value],
+    secret: bool,
+    release: Option<&str>,
+    environment: &str,
+) {
+    for entry in entries {
+        // Inline values are mappings; only plain strings reference files
+        let Some(path) = entry.as_str() else {
+            continue;
+        };
+        let Some(path) = substitute(path, environment, release) else {
+            continue;
+        };
+        references.push(FileReference {
+            path,
+            secret,
+            release: release.map(str::to_string),
+        });
+    }
+}
+
+/// Substitutes `{{ .Environment.Name }}` and `{{ .Release.Name }}` in a
+/// values path. `None` when other template expressions remain.
+pub fn substitute(path: &str, environment: &str, release: Option<&str>) -> Option<String> {
+    let mut path = path
+        .replace("{{ .Environment.Name }}", environment)
+        .replace("{{.Environment.Name}}", environment);
+    if let Some(release) = release {
+        path = path
+            .replace("{{ .Release.Name }}", release)
+            .replace("{{.Release.Name}}", release);
+    }
+    (!path.contains("{{")).then_some(path)
+}
+
+fn string_or_number<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Option::<serde_yaml::Value>::deserialize(deserializer)?;
+    Ok(match value {
+        Some(serde_yaml::Value::String(s)) => Some(s),
+        Some(serde_yaml::Value::Number(n)) => Some(n.to_string()),
+        _ => None,
+    })
+}
@@ -0,0 +1,344 @@
+//! Reads and updates kubeconfig files the way kubectl does, without
+//! shelling out to it.
+
+use crate::utils::private_temp_base;
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// A context from the merged kubeconfig, with its cluster server resolved.
+pub struct Context {
+    pub name: String,
+    pub cluster: String,
+    pub server: Option<String>,
+    pub user: Option<String>,
+    pub namespace: Option<String>,
+}
+
+/// The kubeconfig files kubectl would read, merged.
+pub struct Kubeconfig {
+    pub current_context: Option<String>,
+    pub contexts: Vec<Context>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct File {
+    #[serde(rename = "current-context")]
+    current_context: Option<String>,
+    clusters: Option<Vec<Named<ClusterSpec>>>,
+    contexts: Option<Vec<Named<ContextSpec>>>,
+}
+
+#[derive(Deserialize)]
+struct Named<T> {
+    name: String,
+    #[serde(alias = "cluster", alias = "context")]
+    spec: Option<T>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ClusterSpec {
+    server: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ContextSpec {
+    cluster: Option<String>,
+    user: Option<String>,
+    namespace: Option<String>,
+}
+
+/// Files kubectl reads: every entry of `KUBECONFIG`, else `~/.kube/config`.
+pub fn paths() -> Vec<PathBuf> {
+    match env::var("KUBECONFIG") {
+        Ok(value) if !value.is_empty() => env::split_paths(&value)
+            .filter(|p| !p.as_os_str().is_empty())
+            .collect(),
+        _ => env::var("HOME")
+            .map(|home| PathBuf::from(home).join(".kube").join("config"))
+            .into_iter()
+            .collect(),
+    }
+}
+
+fn read(path: &Path) -> Result<Option<File>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read kubeconfig {}: {}", path.display(), e))?;
+    if content.trim().is_empty() {
+        return Ok(Some(File::default()));
+    }
+    serde_yaml::from_str(&content)
+        .map(Some)
+        .map_err(|e| format!("Failed to parse kubeconfig {}: {}", path.display(), e))
+}
+
+impl Kubeconfig {
+    /// Loads and merges the files from [`paths`].
+    pub fn load() -> Result<Self, String> {
+        Self::from_files(&paths())
+    }
+
+    /// Merges kubeconfig files with kubectl's rules: missing files are
+    /// skipped, and the first file to define a context, cluster or the
+    /// current context wins.
+    pub fn from_files(paths: &[PathBuf]) -> Result<Self, String> {
+        let mut current_context = None;
+        let mut servers: Vec<(String, Option<String>)> = Vec::new();
+        let mut specs: Vec<(String, ContextSpec)> = Vec::new();
+
+        for path in paths {
+            let Some(file) = read(path)? else {
+                continue;
+            };
+
+            if current_context.is_none() {
+                current_context = file.current_context.filter(|c| !c.is_empty());
+            }
+            for cluster in file.clusters.unwrap_or_default() {
+                if !servers.iter().any(|(name, _)| *name == cluster.name) {
+                    let server = cluster.spec.and_then(|s| s.server);
+                    servers.push((cluster.name, server));
+                }
+            }
+            for context in file.contexts.unwrap_or_default() {
+                if !specs.iter().any(|(name, _)| *name == context.name) {
+                    specs.push((context.name, context.spec.unwrap_or_default()));
+                }
+            }
+        }
+
+        let contexts = specs
+            .into_iter()
+            .map(|(name, spec)| {
+                let cluster = spec.cluster.unwrap_or_default();
+                let server = servers
+                    .iter()
+                    .find(|(name, _)| *name == cluster)
+                    .and_then(|(_, server)| server.clone());
+                Context {
+                    name,
+                    cluster,
+                    server,
+                    user: spec.user,
+                    namespace: spec.namespace,
+                }
+            })
+            .collect();
+
+        Ok(Kubeconfig {
+            current_context,
+            contexts,
+        })
+    }
+
+    pub fn context(&self, name: &str) -> Option<&Context> {
+        self.contexts.iter().find(|c| c.name == name)
+    }
+}
+
+/// Makes `name` the current context. Like kubectl, the change goes to the
+/// first file that already sets a current context, else to the first file.
+/// Returns the file that was written.
+pub fn set_current_context(name: &str) -> Result<PathBuf, String> {
+    let paths = paths();
+    let mut target = None;
+    for path in &paths {
+        let sets_current = read(path)?
+            .and_then(|f| f.current_context)
+            .is_some_and(|c| !c.is_empty());
+        if sets_current {
+            target = Some(path.clone());
+            break;
+        }
+    }
+    let target = target
+        .or_else(|| paths.first().cloned())
+        .ok_or("Cannot locate kubeconfig: neither KUBECONFIG nor HOME is set")?;
+
+    let mut document = match fs::read_to_string(&target) {
+        Ok(content) if !content.trim().is_empty() => {
+            serde_yaml::from_str::<serde_yaml::Value>(&content)
+                .map_err(|e| format!("Failed to parse kubeconfig {}: {}", target.display(), e))?
+        }
+        _ => serde_yaml::Value::Mapping(Default::default()),
+    };
+    let mapping = document
+        .as_mapping_mut()
+        .ok_or_else(|| format!("Invalid kubeconfig {}", target.display()))?;
+    mapping.insert("current-context".into(), name.into());
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let content = serde_yaml::to_string(&document)
+        .map_err(|e| format!("Failed to serialize kubeconfig: {}", e))?;
+    fs::write(&target, content)
+        .map_err(|e| format!("Failed to write kubeconfig {}: {}", target.display(), e))?;
+
+    Ok(target)
+}
+
+/// Default namespace of `context`, or of the current context when `None`.
+pub fn context_namespace(context: Option<&str>) -> Option<String> {
+    let kubeconfig = Kubeconfig::load().ok()?;
+    let name = context.or(kubeconfig.current_context.as_deref())?;
+    kubeconfig.context(name)?.namespace.clone()
+}
+
+/// Sets the default namespace of `context` in the first file defining it,
+/// as `kubectl config set-context --namespace` does. Returns that file.
+pub fn set_namespace(context: &str, namespace: &str) -> Result<PathBuf, String> {
+    for path in paths() {
+        if !path.exists() {
+            continue;
+        }
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read kubeconfig {}: {}", path.display(), e))?;
+        let mut document: serde_yaml::Value = serde_yaml::from_str(&content)
+            .map_err(|e| format!("Failed to parse kubeconfig {}: {}", path.display(), e))?;
+
+        let entry = document["contexts"].as_sequence_mut().and_then(|contexts| {
+            contexts
+                .iter_mut()
+                .find(|c| c["name"].as_str() == Some(context))
+        });
+        let Some(entry) = entry else {
+            continue;
+        };
+
+        let spec = entry
+            .as_mapping_mut()
+            .ok_or_else(|| format!("Invalid context {} in {}", context, path.display()))?
+            .entry("context".into())
+            .or_insert_with(|| serde_yaml::Value::Mapping(Default::default()));
+        let spec = spec
+            .as_mapping_mut()
+            .ok_or_else(|| format!("Invalid context {} in {}", context, path.display()))?;
+        spec.insert("namespace".into(), namespace.into());
+
+        let content = serde_yaml::to_string(&document)
+            .map_err(|e| format!("Failed to serialize kubeconfig: {}", e))?;
+        fs::write(&path, content)
+            .map_err(|e| format!("Failed to write kubeconfig {}: {}", path.display(), e))?;
+        return Ok(path);
+    }
+
+    Err(format!("Context not found: {}", context))
+}
+
+/// Keys holding file paths, which kubectl resolves relative to the
+/// kubeconfig file they appear in.
+const PATH_KEYS: [&str; 4] = [
+    "certificate-authority",
+    "client-certificate",
+    "client-key",
+    "tokenFile",
+];
+
+/// A standalone kubeconfig holding only `name`, its cluster and its user,
+/// with `name` as the current context. Relative file references are made
+/// absolute so the result can live anywhere.
+pub fn isolate(name: &str) -> Result<serde_yaml::Value, String> {
+    let mut files = Vec::new();
+    for path in paths() {
+        if !path.exists() {
+            continue;
+        }
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read kubeconfig {}: {}", path.display(), e))?;
+        let value: serde_yaml::Value = serde_yaml::from_str(&content)
+            .map_err(|e| format!("Failed to parse kubeconfig {}: {}", path.display(), e))?;
+        files.push((path, value));
+    }
+
+    // First definition wins, as when merging
+    let find = |section: &str, entry: &str| {
+        files.iter().find_map(|(path, value)| {
+            let item = value[section]
+                .as_sequence()?
+                .iter()
+                .find(|item| item["name"].as_str() == Some(entry))?;
+            Some((path.parent().unwrap_or(Path::new(".")), item.clone()))
+        })
+    };
+
+    let (_, context) =
+        find("contexts", name).ok_or_else(|| format!("Context not found: {}", name))?;
+    let cluster_name = context["context"]["cluster"].as_str().unwrap_or_default();
+    let user_name = context["context"]["user"].as_str().unwrap_or_default();
+
+    let mut clusters = Vec::new();
+    if let Some((dir, mut cluster)) = find("clusters", cluster_name) {
+        absolutize(&mut cluster["cluster"], dir);
+        clusters.push(cluster);
+    }
+    let mut users = Vec::new();
+    if let Some((dir, mut user)) = find("users", user_name) {
+        absolutize(&mut user["user"], dir);
+        users.push(user);
+    }
+
+    let mut document = serde_yaml::Mapping::new();
+    document.insert("apiVersion".into(), "v1".into());
+    document.insert("kind".into(), "Config".into());
+    document.insert("current-context".into(), name.into());
+    document.insert("clusters".into(), clusters.into());
+    document.insert("contexts".into(), vec![context].into());
+    document.insert("users".into(), users.into());
+    Ok(serde_yaml::Value::Mapping(document))
+}
+
+fn absolutize(section: &mut serde_yaml::Value, dir: &Path) {
+    let Some(mapping) = section.as_mapping_mut() else {
+        return;
+    };
+    for key in PATH_KEYS {
+        if let Some(serde_yaml::Value::String(path)) = mapping.get_mut(key) {
+            if Path::new(path.as_str()).is_relative() {
+                *path = dir.join(&*path).to_string_lossy().into_owned();
+            }
+        }
+    }
+}
+
+/// Writes [`isolate`]d `name` to a new file only the current user can
+/// read, with an unpredictable name under [`private_temp_base`], and
+/// returns its path. The caller owns the file.
+pub fn write_isolated(name: &str) -> Result<PathBuf, String> {
+    let content = serde_yaml::to_string(&isolate(name)?)
+        .map_err(|e| format!("Failed to serialize kubeconfig: {}", e))?;
+
+    let safe_name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    // Created exclusively with mode 0600, never following an existing path
+    let base = private_temp_base();
+    let mut file = tempfile::Builder::new()
+        .prefix(&format!("helmctl-kubeconfig-{}-", safe_name))
+        .tempfile_in(&base)
+        .map_err(|e| format!("Failed to create a kubeconfig in {}: {}", base.display(), e))?;
+    file.write_all(content.as_bytes())
+        .map_err(|e| format!("Failed to write {}: {}", file.path().display(), e))?;
+
+    let (_, path) = file
+        .keep()
+        .map_err(|e| format!("Failed to keep {}: {}", e.file.path().display(), e))?;
+    Ok(path)
+}
@@ -3,9 +3,17 @@
 //! This library provides the core functionality for the Helmctl CLI tool.
 //! It includes configuration management, utilities, and command implementations.
 
+pub mod ci;
 pub mod cli;
 pub mod config;
+pub mod diff;
+pub mod helmfile;
+pub mod manifest;
+pub mod redact;
+pub mod schema;
+pub mod tools;
 pub mod utils;
+pub mod version;
 
 // Re-export commonly used items
 pub use config::{Config, Repository};
@@ -1,26 +1,98 @@
 use clap::Parser;
 use colored::*;
 
+mod ci;
 mod cli;
+mod cluster;
 mod commands;
 mod config;
+mod diff;
+mod helmfile;
+mod kubeconfig;
+mod manifest;
+mod plugins;
+mod redact;
+mod schema;
+mod secrets;
+mod tools;
 mod utils;
+mod version;
 
+use ci::esay;
 use cli::*;
 use commands::*;
 use config::Config;
 
 fn main() {
     let cli = Cli::parse();
+    ci::init(cli.ci, cli.yes);
 
-    // Load configuration
-    let config = Config::load(&cli.config).unwrap_or_default();
+    // Load configuration. The config subcommands load, repair or inspect
+    // the file themselves and must still run when it is invalid.
+    let config_optional = matches!(
+        cli.command,
+        Commands::Config { .. } | Commands::Doctor { .. }
+    );
+
+    // Commands whose stdout is meant to be piped into other tools
+    let machine_output = matches!(
+        cli.command,
+        Commands::Config {
+            action: ConfigAction::Schema
+        } | Commands::Doctor {
+            output: OutputFormat::Json
+        } | Commands::Context {
+            action: ContextAction::Env { .. }
+        } | Commands::Values {
+            output: OutputFormat::Json,
+            ..
+        }
+    );
+    let config = match Config::resolve(cli.config.as_deref()) {
+        Ok(resolved) => resolved.config,
+        Err(e) if cli.ignore_config_errors || config_optional => {
+            if !config_optional {
+                esay!("{} {}", "⚠️  Ignoring configuration error:".yellow(), e);
+            }
+            Config::default()
+        }
+        Err(e) => {
+            eprintln!("{} {}", "✗ Error:".red().bold(), e);
+            eprintln!("Run 'helmctl config validate' for details or pass --ignore-config-errors");
+            std::process::exit(1);
+        }
+    };
+
+    // Profiles fill in environment, context and namespace between the
+    // flags and the configured defaults
+    let profile_name = match &cli.command {
+        Commands::Deploy { profile, .. }
+        | Commands::K8sDeploy { profile, .. }
+        | Commands::Status { profile, .. } => profile.as_deref(),
+        _ => None,
+    };
+    let profile = match config.profile(profile_name) {
+        Ok(profile) => profile,
+        Err(e) => {
+            eprintln!("{} {}", "✗ Error:".red().bold(), e);
+            std::process::exit(1);
+        }
+    };
+
+    // Mask configured secret patterns in all output
+    redact::init(config.redact_patterns.as_deref().unwrap_or_default());
+    tools::init(config.tools.as_ref());
+    plugins::init(config.plugins.as_ref());
+    ci::init_summary(config.ci_summary_file.as_deref());
 
     // Initialize logging if specified
     if let Some(log_file) = &cli.log_file {
         utils::init_logging(log_file);
     }
 
+    // Set by diff modes that found pending changes
+    let mut changes_detected = false;
+
     let result = match cli.command {
         Commands::Lint {
             file,
@@ -29,6 +101,7 @@ fn main() {
             template_only,
         } => {
             let env = environment.or(config.default_environment.clone());
+            let strict = strict.or(config.default_strict).unwrap_or(false);
             let result = lint::execute(&file, env.as_deref(), strict, template_only, cli.verbose);
             utils::log_operation(
                 "lint",
@@ -42,25 +115,31 @@ fn main() {
             environment,
             dry_run,
             diff,
+            detailed_exitcode,
             skip_deps,
             concurrency,
             context,
+            install_missing,
+            profile: _,
         } => {
-            let env = environment.or(config.default_environment.clone());
-            let conc = if concurrency == 1 {
-                config.default_concurrency.unwrap_or(1)
-            } else {
-                concurrency
-            };
-            let ctx = context.or(config.preferred_context.clone());
+            let env = environment
+                .or(profile.environment)
+                .or(config.default_environment.clone());
+            let conc = concurrency.or(config.default_concurrency).unwrap_or(1);
+            let skip_deps = skip_deps.or(config.default_skip_deps).unwrap_or(false);
+            let ctx = context
+                .or(profile.context)
+                .or(config.preferred_context.clone());
             let result = deploy::execute(
                 &file,
                 env.as_deref(),
                 dry_run,
                 diff,
+                detailed_exitcode,
                 skip_deps,
                 conc,
                 ctx.as_deref(),
+                install_missing,
                 cli.verbose,
             );
             utils::log_operation(
@@ -68,37 +147,71 @@ fn main() {
                 &format!("file: {}, env: {:?}, dry_run: {}", file, env, dry_run),
                 result.is_ok(),
             );
-            result
+            result.map(|changes| changes_detected = changes)
         }
         Commands::K8sDeploy {
             manifest,
             namespace,
             context,
             dry_run,
+            server_side,
+            field_manager,
+            force_conflicts,
+            prune,
+            selector,
+            diff,
+            detailed_exitcode,
             wait,
             timeout,
+            profile: _,
         } => {
-            let ctx = context.or(config.preferred_context.clone());
-            let to = if timeout == 300 {
-                config.default_timeout.unwrap_or(300)
+            let ctx = context
+                .or(profile.context)
+                .or(config.preferred_context.clone());
+            // The context's own namespace is left to kubectl, so objects
+            // that set a namespace keep it
+            let namespace = namespace
+                .or(profile.namespace)
+                .or(config.default_namespace.clone());
+            let wait = wait.or(config.default_wait).unwrap_or(false);
+            let to = timeout.or(config.default_timeout).unwrap_or(300);
+            let options = k8s::ApplyOptions {
+                dry_run,
+                server_side,
+                field_manager,
+                force_conflicts,
+                prune,
+                selector,
+            };
+            let result = if diff {
+                k8s::diff(
+                    &manifest,
+                    namespace.as_deref(),
+                    ctx.as_deref(),
+                    &options,
+                    cli.verbose,
+                )
             } else {
-                timeout
+                k8s::execute(
+                    &manifest,
+                    namespace.as_deref(),
+                    ctx.as_deref(),
+                    &options,
+                    wait,
+                    to,
+                    cli.verbose,
+                )
+                .map(|_| false)
             };
-            let result = k8s::execute(
-                &manifest,
-                namespace.as_deref(),
-                ctx.as_deref(),
-                dry_run,
-                wait,
-                to,
-                cli.verbose,
-            );
             utils::log_operation(
                 "k8s-deploy",
-                &format!("manifest: {}, dry_run: {}", manifest, dry_run),
+                &format!(
+                    "manifest: {}, dry_run: {:?}, diff: {}",
+                    manifest, dry_run, diff
+                ),
                 result.is_ok(),
             );
-            result
+            result.map(|changes| changes_detected = changes && detailed_exitcode)
         }
         Commands::Rollback {
             file,
@@ -129,16 +242,35 @@ fn main() {
             environment,
             release,
             context,
+            namespace,
             detailed,
+            watch,
+            interval,
+            timeout,
+            profile: _,
         } => {
-            let env = environment.or(config.default_environment.clone());
-            let ctx = context.or(config.preferred_context.clone());
+            let env = environment
+                .or(profile.environment)
+                .or(config.default_environment.clone());
+            let ctx = context
+                .or(profile.context)
+                .or(config.preferred_context.clone());
+            let namespace = namespace
+                .or(profile.namespace)
+                .or(config.default_namespace.clone())
+                .or_else(|| kubeconfig::context_namespace(ctx.as_deref()));
+            let detailed = detailed.or(config.default_detailed).unwrap_or(false);
+            let to = timeout.or(config.default_timeout).unwrap_or(300);
             let result = status::execute(
                 &file,
                 env.as_deref(),
                 release.as_deref(),
                 ctx.as_deref(),
+                namespace.as_deref(),
                 detailed,
+                watch,
+                interval,
+                to,
                 cli.verbose,
             );
             utils::log_operation(
@@ -162,17 +294,124 @@ fn main() {
             );
             result
         }
-        Commands::Config { action } => config_cmd::execute(action, &cli.config),
-        Commands::Context { action } => context::execute(action),
+        Commands::Render {
+            file,
+            environment,
+            out_dir,
+            clean,
+        } => {
+            let env = environment.or(config.default_environment.clone());
+            let result = render::execute(&file, env.as_deref(), &out_dir, clean, cli.verbose);
+            utils::log_operation(
+                "render",
+                &format!("file: {}, env: {:?}, out_dir: {}", file, env, out_dir),
+                result.is_ok(),
+            );
+            result
+        }
+        Commands::Compare {
+            file,
+            environment,
+            ignore,
+        } => {
+            let result = compare::execute(&file, &environment, &ignore, cli.verbose);
+            utils::log_operation(
+                "compare",
+                &format!("file: {}, environments: {:?}", file, environment),
+                result.is_ok(),
+            );
+            result.map(|changes| changes_detected = changes)
+        }
+        Commands::DiffRev {
+            base,
+            head,
+            file,
+            environment,
+            ignore,
+        } => {
+            let env = environment.or(config.default_environment.clone());
+            let result = diff_rev::execute(
+                &file,
+                &base,
+                head.as_deref(),
+                env.as_deref(),
+                &ignore,
+                cli.verbose,
+            );
+            utils::log_operation(
+                "diff-rev",
+                &format!(
+                    "file: {}, base: {}, head: {:?}, env: {:?}",
+                    file, base, head, env
+                ),
+                result.is_ok(),
+            );
+            result.map(|changes| changes_detected = changes)
+        }
+        Commands::Outdated {
+            file,
+            release,
+            repo_cache,
+            offline,
+        } => {
+            let result = outdated::execute(
+                &file,
+                release.as_deref(),
+                repo_cache.as_deref(),
+                offline,
+                cli.verbose,
+            );
+            utils::log_operation(
+                "outdated",
+                &format!("file: {}, release: {:?}", file, release),
+                result.is_ok(),
+            );
+            result
+        }
+        Commands::Config { action } => config_cmd::execute(action, cli.config.as_deref()),
+        Commands::Secrets { action } => {
+            let details = format!("{:?}", action);
+            let result = secrets_cmd::execute(action, cli.verbose);
+            utils::log_operation("secrets", &details, result.is_ok());
+            result
+        }
+        Commands::Plugins { action } => plugins_cmd::execute(action, cli.verbose),
+        Commands::Values {
+            release,
+            file,
+            environment,
+            set,
+            compare,
+            output,
+        } => {
+            let env = environment.or(config.default_environment.clone());
+            values::execute(
+                &file,
+                &release,
+                env.as_deref(),
+                &set,
+                compare,
+                output,
+                cli.verbose,
+            )
+        }
+        Commands::Doctor { output } => doctor::execute(cli.config.as_deref(), output),
+        Commands::Context { action } => context::execute(action, &config, cli.config.as_deref()),
     };
 
     match result {
+        Ok(_) if changes_detected => {
+            println!("{}", "△ Changes detected".yellow().bold());
+            std::process::exit(utils::EXIT_CODE_CHANGES);
+        }
         Ok(_) => {
-            println!("{}", "✓ Operation completed successfully".green().bold());
+            if !machine_output {
+                println!("{}", "✓ Operation completed successfully".green().bold());
+            }
             std::process::exit(0);
         }
         Err(e) => {
-            eprintln!("{} {}", "✗ Error:".red().bold(), e);
+            eprintln!("{} {}", "✗ Error:".red().bold(), redact::redact(&e));
             std::process::exit(1);
         }
     }
@@ -0,0 +1,147 @@
+use serde::Deserialize;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+/// A single Kubernetes object read from a manifest stream.
+pub struct Object {
+    pub kind: String,
+    pub name: String,
+    pub namespace: Option<String>,
+}
+
+impl Object {
+    /// `kind/name` reference as accepted by kubectl.
+    pub fn reference(&self) -> String {
+        format!("{}/{}", self.kind.to_lowercase(), self.name)
+    }
+}
+
+/// Parses a multi-document YAML stream into objects, expanding `List`
+/// kinds and skipping empty documents.
+pub fn parse(content: &str) -> Result<Vec<Object>, String> {
+    Ok(parse_documents(content)?.iter().map(Object::from).collect())
+}
+
+/// Reads objects from a manifest file or, recursively, from every YAML
+/// and JSON file in a directory.
+pub fn load(path: &Path) -> Result<Vec<Object>, String> {
+    Ok(load_documents(path)?.iter().map(Object::from).collect())
+}
+
+/// Like [`parse`], but returns the full documents as JSON.
+pub fn parse_documents(content: &str) -> Result<Vec<Value>, String> {
+    let mut documents = Vec::new();
+
+    for document in serde_yaml::Deserializer::from_str(content) {
+        let value = serde_yaml::Value::deserialize(document)
+            .map_err(|e| format!("Failed to parse manifest: {}", e))?;
+        collect(value, &mut documents)?;
+    }
+
+    Ok(documents)
+}
+
+/// Like [`load`], but returns the full documents as JSON.
+pub fn load_documents(path: &Path) -> Result<Vec<Value>, String> {
+    if path.is_dir() {
+        let mut entries: Vec<_> = fs::read_dir(path)
+            .map_err(|e| format!("Failed to read directory {}: {}", path.display(), e))?
+            .filter_map(Result::ok)
+            .map(|e| e.path())
+            .collect();
+        entries.sort();
+
+        let mut documents = Vec::new();
+        for entry in entries {
+            let is_manifest = entry
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| matches!(e, "yaml" | "yml" | "json"));
+            if entry.is_dir() || is_manifest {
+                documents.extend(load_documents(&entry)?);
+            }
+        }
+        return Ok(documents);
+    }
+
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read manifest {}: {}", path.display(), e))?;
+    parse_documents(&content)
+}
+
+fn collect(value: serde_yaml::Value, documents: &mut Vec<Value>) -> Result<(), String> {
+    let Some(kind) = value["kind"].as_str() else {
+        return Ok(());
+    };
+
+    // `kind: List`, or a typed list such as `PodList` from `kubectl get`;
+    // custom kinds merely ending in "List" are regular objects
+    let is_list = kind == "List" || (value["apiVersion"] == "v1" && kind.ends_with("List"));
+    if let (true, Some(items)) = (is_list, value["items"].as_sequence()) {
+        for item in items {
+            collect(item.clone(), documents)?;
+        }
+        return Ok(());
+    }
+
+    if value["metadata"]["name"].as_str().is_none() {
+        return Ok(());
+    }
+
+    let document =
+        serde_json::to_value(&value).map_err(|e| format!("Failed to parse manifest: {}", e))?;
+    documents.push(document);
+    Ok(())
+}
+
+impl From<&Value> for Object {
+    fn from(document: &Value) -> Self {
+        Object {
+            kind: document["kind"].as_str().unwrap_or_default().to_string(),
+            name: document["metadata"]["name"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            namespace: document["metadata"]["namespace"]
+                .as_str()
+                .map(str::to_string),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_expands_lists() {
+        let content = r#"
+apiVersion: v1
+kind: List
+items:
+  - apiVersion: apps/v1
+    kind: Deployment
+    metadata:
+      name: web
+      namespace: shop
+  - apiVersion: batch/v1
+    kind: Job
+    metadata:
+      name: migrate
+---
+---
+apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: settings
+"#;
+        let objects = parse(content).unwrap();
+        let references: Vec<String> = objects.iter().map(|o| o.reference()).collect();
+        assert_eq!(
+            references,
+            ["deployment/web", "job/migrate", "configmap/settings"]
+        );
+        assert_eq!(objects[0].namespace.as_deref(), Some("shop"));
+    }
+}
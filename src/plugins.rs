@@ -0,0 +1,353 @@
+use crate::ci::say;
+use crate::tools;
+use crate::utils::print_command;
+use crate::version::Version;
+use colored::*;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{self, Stdio};
+use std::sync::OnceLock;
+
+static SOURCES: OnceLock<BTreeMap<String, String>> = OnceLock::new();
+
+/// Registers plugin install sources from `plugins` in helmctl.yaml, e.g.
+/// `diff: /opt/plugins/helm-diff-linux-amd64.tgz`. Only the first call
+/// has an effect.
+pub fn init(sources: Option<&BTreeMap<String, String>>) {
+    let _ = SOURCES.set(sources.cloned().unwrap_or_default());
+}
+
+/// A helm plugin as reported by `helm plugin list`.
+pub struct HelmPlugin {
+    pub name: String,
+    pub version: String,
+}
+
+/// A helm plugin helmctl depends on.
+pub struct RequiredPlugin {
+    pub name: &'static str,
+    pub minimum: &'static str,
+    /// Installed from here unless `plugins.<name>` in helmctl.yaml says otherwise
+    pub default_source: &'static str,
+    pub purpose: &'static str,
+}
+
+/// `diff` 3.4.0 is the oldest release supporting `--detailed-exitcode`
+/// together with `--suppress-secrets`.
+pub const REQUIRED_PLUGINS: [RequiredPlugin; 1] = [RequiredPlugin {
+    name: "diff",
+    minimum: "3.4.0",
+    default_source: "https://github.com/databus23/helm-diff",
+    purpose: "deploy --diff",
+}];
+
+pub fn required(name: &str) -> Option<&'static RequiredPlugin> {
+    REQUIRED_PLUGINS.iter().find(|p| p.name == name)
+}
+
+/// Parses the `NAME VERSION DESCRIPTION` table printed by `helm plugin
+/// list`.
+pub fn parse_plugin_list(output: &str) -> Vec<HelmPlugin> {
+    output
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .skip_while(|line| !line.trim_start().starts_with("NAME"))
+        .skip(1)
+        .filter_map(|line| {
+            let mut columns = line.split_whitespace();
+            Some(HelmPlugin {
+                name: columns.next()?.to_string(),
+                version: columns.next().unwrap_or_default().to_string(),
+            })
+        })
+        .collect()
+}
+
+pub fn installed() -> Result<Vec<HelmPlugin>, String> {
+    let output = tools::command("helm")
+        .args(["plugin", "list"])
+        .output()
+        .map_err(|e| format!("Failed to check helm plugins: {}", e))?;
+
+    if !output.status.success() {
+        return Err("Failed to check helm plugins".to_string());
+    }
+
+    Ok(parse_plugin_list(&String::from_utf8_lossy(&output.stdout)))
+}
+
+impl RequiredPlugin {
+    pub fn minimum_version(&self) -> Version {
+        Version::parse(self.minimum).expect("valid minimum version")
+    }
+
+    /// Checks the installed plugins for this one, returning its version.
+    pub fn check(&self, installed: &[HelmPlugin]) -> Result<Version, String> {
+        let Some(plugin) = installed.iter().find(|p| p.name == self.name) else {
+            return Err(format!(
+                "Helm {} plugin not installed (needed for {}). Install it with: helmctl plugins install {}",
+                self.name, self.purpose, self.name
+            ));
+        };
+
+        let minimum = self.minimum_version();
+        match Version::parse(&plugin.version) {
+            Some(version) if version >= minimum => Ok(version),
+            Some(version) => Err(format!(
+                "Helm {} plugin {} is older than the minimum supported version {}. Upgrade it with: helmctl plugins install {}",
+                self.name, version, minimum, self.name
+            )),
+            None => Err(format!(
+                "Cannot determine the version of the helm {} plugin ('{}')",
+                self.name, plugin.version
+            )),
+        }
+    }
+
+    /// Installs (or replaces) the plugin from `source`, falling back to
+    /// the configured or default source. Local `.tgz`/`.tar.gz` archives
+    /// are unpacked so installs work without network access; directories
+    /// and URLs go through `helm plugin install`. Either way the plugin is
+    /// staged first, and an installed version is only replaced once the
+    /// new one is ready and recent enough. It is restored if helm then
+    /// does not report the new one as usable.
+    pub fn install(&self, source: Option<&str>, verbose: bool) -> Result<(), String> {
+        let source = source
+            .or_else(|| {
+                SOURCES
+                    .get()
+                    .and_then(|s| s.get(self.name))
+                    .map(String::as_str)
+            })
+            .unwrap_or(self.default_source);
+
+        say!(
+            "📥 Installing helm {} plugin from {}",
+            self.name,
+            source.yellow()
+        );
+
+        // Staged inside helm's plugin directory, so the final move is a
+        // rename on the same filesystem. helm only loads plugins from
+        // direct subdirectories, so the staged copy stays invisible.
+        let plugins_dir = helm_plugins_dir()?;
+        let staging = plugins_dir.join(format!(".helmctl-install-{}", process::id()));
+        fs::create_dir_all(&staging)
+            .map_err(|e| format!("Failed to create {}: {}", staging.display(), e))?;
+
+        // Kept when the previous plugin could not be put back, since its
+        // only copy is in there
+        let mut keep_staging = false;
+        let result = self
+            .replace(source, &plugins_dir, &staging, verbose)
+            .and_then(|swap| {
+                installed()
+                    .and_then(|plugins| self.check(&plugins))
+                    .map_err(|e| match swap.restore() {
+                        Ok(true) => format!("{}; the previous version was restored", e),
+                        Ok(false) => e,
+                        Err(restore) => {
+                            keep_staging = true;
+                            format!("{}; {}", e, restore)
+                        }
+                    })
+            });
+        if !keep_staging {
+            let _ = fs::remove_dir_all(&staging);
+        }
+        let version = result?;
+
+        say!(
+            "{}",
+            format!("✅ Installed helm {} plugin {}", self.name, version).green()
+        );
+        Ok(())
+    }
+
+    /// Installs `source` into `staging`, then swaps it in for the installed
+    /// plugin, restoring the previous one if the swap fails. The previous
+    /// one stays in `staging` so the caller can still restore it.
+    fn replace(
+        &self,
+        source: &str,
+        plugins_dir: &Path,
+        staging: &Path,
+        verbose: bool,
+    ) -> Result<Swap, String> {
+        let unpacked = staging.join("new");
+        fs::create_dir_all(&unpacked)
+            .map_err(|e| format!("Failed to create {}: {}", unpacked.display(), e))?;
+
+        if is_archive(source) {
+            unpack_archive(Path::new(source), &unpacked, verbose)?;
+        } else {
+            let mut cmd = tools::command("helm");
+            cmd.args(["plugin", "install", source])
+                .env("HELM_PLUGINS", &unpacked);
+            if verbose {
+                print_command(&cmd);
+            }
+
+            let output = cmd
+                .stdin(Stdio::null())
+                .output()
+                .map_err(|e| format!("Failed to execute helm plugin install: {}", e))?;
+            if !output.status.success() {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                return Err(format!(
+                    "Failed to install helm {} plugin:\n{}",
+                    self.name, stderr
+                ));
+            }
+        }
+
+        let staged = find_plugin_dir(&unpacked, self.name).ok_or_else(|| {
+            format!(
+                "{} does not contain the helm {} plugin; the installed version was kept",
+                source, self.name
+            )
+        })?;
+        self.check_staged(source, &staged)?;
+
+        let previous = find_plugin_dir(plugins_dir, self.name);
+        let backup = staging.join("previous");
+        if let Some(previous) = &previous {
+            fs::rename(previous, &backup)
+                .map_err(|e| format!("Failed to move aside {}: {}", previous.display(), e))?;
+        }
+
+        let target = plugins_dir.join(staged.file_name().unwrap_or_default());
+        if let Err(e) = fs::rename(&staged, &target) {
+            if let Some(previous) = &previous {
+                let _ = fs::rename(&backup, previous);
+            }
+            return Err(format!(
+                "Failed to install helm {} plugin into {}: {}; the installed version was kept",
+                self.name,
+                target.display(),
+                e
+            ));
+        }
+        Ok(Swap {
+            target,
+            previous: previous.map(|previous| (backup, previous)),
+        })
+    }
+
+    /// Rejects a staged plugin older than the minimum version before it
+    /// replaces the installed one.
+    fn check_staged(&self, source: &str, staged: &Path) -> Result<(), String> {
+        let minimum = self.minimum_version();
+        let version = fs::read_to_string(staged.join("plugin.yaml"))
+            .ok()
+            .and_then(|manifest| serde_yaml::from_str::<serde_yaml::Value>(&manifest).ok())
+            .and_then(|manifest| match &manifest["version"] {
+                serde_yaml::Value::String(v) => Some(v.clone()),
+                serde_yaml::Value::Number(v) => Some(v.to_string()),
+                _ => None,
+            });
+
+        match version.as_deref().and_then(Version::parse) {
+            Some(version) if version >= minimum => Ok(()),
+            Some(version) => Err(format!(
+                "{} provides helm {} plugin {}, older than the minimum supported version {}; the installed version was kept",
+                source, self.name, version, minimum
+            )),
+            None => Err(format!(
+                "Cannot determine the version of the helm {} plugin in {}; the installed version was kept",
+                self.name, source
+            )),
+        }
+    }
+}
+
+/// A plugin swapped in by [`RequiredPlugin::replace`].
+struct Swap {
+    target: PathBuf,
+    /// Backup in the staging directory and where it was installed
+    previous: Option<(PathBuf, PathBuf)>,
+}
+
+impl Swap {
+    /// Puts the previous plugin back, returning whether there was one.
+    fn restore(self) -> Result<bool, String> {
+        let Some((backup, previous)) = self.previous else {
+            return Ok(false);
+        };
+        let _ = fs::remove_dir_all(&self.target);
+        fs::rename(&backup, &previous).map_err(|e| {
+            format!(
+                "Failed to restore {} from {}: {}",
+                previous.display(),
+                backup.display(),
+                e
+            )
+        })?;
+        Ok(true)
+    }
+}
+
+/// Checks a required plugin, installing it first when `install_missing`
+/// is set and it is absent or too old.
+pub fn ensure(plugin: &RequiredPlugin, install_missing: bool, verbose: bool) -> Result<(), String> {
+    match plugin.check(&installed()?) {
+        Ok(_) => Ok(()),
+        Err(e) if install_missing => {
+            say!("⚠️  {}", e);
+            plugin.install(None, verbose)
+        }
+        Err(e) => Err(format!("{} or pass --install-missing", e)),
+    }
+}
+
+/// The directory under `dir` holding the plugin called `name`, which need
+/// not match the directory name (e.g. `helm-diff` for `diff`).
+fn find_plugin_dir(dir: &Path, name: &str) -> Option<PathBuf> {
+    fs::read_dir(dir).ok()?.flatten().find_map(|entry| {
+        let path = entry.path();
+        let manifest = fs::read_to_string(path.join("plugin.yaml")).ok()?;
+        let manifest: serde_yaml::Value = serde_yaml::from_str(&manifest).ok()?;
+        (manifest["name"].as_str() == Some(name)).then_some(path)
+    })
+}
+
+fn is_archive(source: &str) -> bool {
+    (source.ends_with(".tgz") || source.ends_with(".tar.gz")) && Path::new(source).is_file()
+}
+
+fn helm_plugins_dir() -> Result<PathBuf, String> {
+    let output = tools::command("helm")
+        .args(["env", "HELM_PLUGINS"])
+        .output()
+        .map_err(|e| format!("Failed to execute helm env: {}", e))?;
+
+    let dir = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || dir.is_empty() {
+        return Err("Cannot determine helm's plugin directory".to_string());
+    }
+    Ok(PathBuf::from(dir))
+}
+
+/// Unpacks a plugin release archive, which holds a single top-level
+/// plugin directory, into `dir`.
+fn unpack_archive(archive: &Path, dir: &Path, verbose: bool) -> Result<(), String> {
+    let mut cmd = tools::command("tar");
+    cmd.arg("-xzf").arg(archive).arg("-C").arg(dir);
+    if verbose {
+        print_command(&cmd);
+    }
+
+    let output = cmd
+        .output()
+        .map_err(|e| format!("Failed to execute tar: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "Failed to unpack {}:\n{}",
+            archive.display(),
+            stderr
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,358 @@
+//! A small JSON Schema validator covering the keywords used by the
+//! helmctl.yaml schema, reporting issues with the YAML source line.
+
+use serde_json::Value as Schema;
+use serde_yaml::Value;
+use std::fmt;
+
+/// One problem found while validating a document.
+pub struct Issue {
+    pub path: String,
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}: {}", line, self.path, self.message),
+            None => write!(f, "{}: {}", self.path, self.message),
+        }
+    }
+}
+
+/// One step of a dotted path such as `repositories[1].url`.
+#[derive(Clone)]
+pub enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// Validates `value` against `schema`, using `source` to attach line
+/// numbers to each issue.
+pub fn validate(value: &Value, schema: &Schema, source: &str) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let mut path = Vec::new();
+    walk(value, schema, &mut path, &mut issues);
+
+    for issue in &mut issues {
+        issue.line = locate(source, &issue.path);
+    }
+    issues
+}
+
+fn walk(value: &Value, schema: &Schema, path: &mut Vec<Segment>, issues: &mut Vec<Issue>) {
+    // Optional keys are written as null by `config init`
+    if value.is_null() {
+        return;
+    }
+
+    if let Some(expected) = schema["type"].as_str() {
+        if !matches_type(value, expected) {
+            issues.push(issue(
+                path,
+                format!("expected {}, found {}", expected, describe(value)),
+            ));
+            return;
+        }
+    }
+
+    if let Some(allowed) = schema["enum"].as_array() {
+        let matches = allowed.iter().any(|a| match (a.as_str(), value.as_str()) {
+            (Some(a), Some(v)) => a == v,
+            _ => false,
+        });
+        if !matches {
+            let options: Vec<&str> = allowed.iter().filter_map(Schema::as_str).collect();
+            issues.push(issue(
+                path,
+                format!(
+                    "invalid value {}, expected one of: {}",
+                    describe(value),
+                    options.join(", ")
+                ),
+            ));
+        }
+    }
+
+    if let (Some("uri"), Some(text)) = (schema["format"].as_str(), value.as_str()) {
+        if !has_scheme(text) {
+            issues.push(issue(
+                path,
+                format!(
+                    "invalid URL {}, expected a scheme such as https://",
+                    describe(value)
+                ),
+            ));
+        }
+    }
+
+    if let Some(n) = value.as_f64() {
+        if let Some(min) = schema["minimum"].as_f64() {
+            if n < min {
+                issues.push(issue(
+                    path,
+                    format!("value {} is below the minimum of {}", n, min),
+                ));
+            }
+        }
+        if let Some(max) = schema["maximum"].as_f64() {
+            if n > max {
+                issues.push(issue(
+                    path,
+                    format!("value {} is above the maximum of {}", n, max),
+                ));
+            }
+        }
+    }
+
+    if let Some(mapping) = value.as_mapping() {
+        let properties = schema["properties"].as_object();
+
+        if let Some(required) = schema["required"].as_array() {
+            for key in required.iter().filter_map(Schema::as_str) {
+                if !mapping.contains_key(key) {
+                    issues.push(issue(path, format!("missing required key '{}'", key)));
+                }
+            }
+        }
+
+        for (key, child) in mapping {
+            let key = match key.as_str() {
+                Some(key) => key.to_string(),
+                None => {
+                    issues.push(issue(
+                        path,
+                        format!("keys must be strings, found {}", describe(key)),
+                    ));
+                    continue;
+                }
+            };
+
+            let child_schema = properties
+                .and_then(|p| p.get(&key))
+                .or_else(|| Some(&schema["additionalProperties"]).filter(|s| s.is_object()));
+
+            path.push(Segment::Key(key.clone()));
+            match child_schema {
+                Some(child_schema) => walk(child, child_schema, path, issues),
+                None if schema["additionalProperties"] == Schema::Bool(false) => {
+                    let known: Vec<&str> = properties
+                        .map(|p| p.keys().map(String::as_str).collect())
+                        .unwrap_or_default();
+                    issues.push(issue(
+                        path,
+                        format!("unknown key '{}' (known keys: {})", key, known.join(", ")),
+                    ));
+                }
+                None => {}
+            }
+            path.pop();
+        }
+    }
+
+    if let (Some(items), Some(item_schema)) = (value.as_sequence(), schema.get("items")) {
+        for (i, item) in items.iter().enumerate() {
+            path.push(Segment::Index(i));
+            walk(item, item_schema, path, issues);
+            path.pop();
+        }
+    }
+}
+
+fn issue(path: &[Segment], message: String) -> Issue {
+    Issue {
+        path: render_path(path),
+        line: None,
+        message,
+    }
+}
+
+/// Whether `text` starts with a URI scheme and `://`, e.g. `https://` or
+/// `oci://`.
+fn has_scheme(text: &str) -> bool {
+    text.split_once("://").is_some_and(|(scheme, _)| {
+        scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    })
+}
+
+fn matches_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "string" => value.is_string(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_bool(),
+        "array" => value.is_sequence(),
+        "object" => value.is_mapping(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn describe(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Bool(b) => format!("boolean {}", b),
+        Value::Number(n) => format!("number {}", n),
+        Value::String(s) => format!("string \"{}\"", s),
+        Value::Sequence(_) => "array".to_string(),
+        Value::Mapping(_) => "object".to_string(),
+        Value::Tagged(_) => "tagged value".to_string(),
+    }
+}
+
+/// Renders segments back into dotted form, e.g. `repositories[1].url`.
+pub fn render_path(path: &[Segment]) -> String {
+    let mut rendered = String::new();
+    for segment in path {
+        match segment {
+            Segment::Key(key) => {
+                if !rendered.is_empty() {
+                    rendered.push('.');
+                }
+                rendered.push_str(key);
+            }
+            Segment::Index(i) => rendered.push_str(&format!("[{}]", i)),
+        }
+    }
+    if rendered.is_empty() {
+        rendered.push_str("(root)");
+    }
+    rendered
+}
+
+/// Finds the 1-based source line of a dotted path such as
+/// `repositories[1].url` by following indentation in block-style YAML.
+/// Returns `None` for flow-style documents it cannot follow.
+pub fn locate(source: &str, path: &str) -> Option<usize> {
+    let lines: Vec<&str> = source.lines().collect();
+    let mut cursor = 0;
+    let mut min_indent = 0;
+    let mut found = None;
+
+    for segment in parse_path(path) {
+        let (line, indent) = match segment {
+            Segment::Key(key) => find_key(&lines, cursor, min_indent, &key)?,
+            Segment::Index(index) => find_item(&lines, cursor, min_indent, index)?,
+        };
+        found = Some(line);
+        cursor = line;
+        min_indent = indent;
+    }
+
+    found.map(|line| line + 1)
+}
+
+/// Finds `key:` below `cursor` at `min_indent` or deeper, stopping when
+/// the enclosing block ends. Returns the line and the indentation its
+/// children must exceed.
+fn find_key(lines: &[&str], cursor: usize, min_indent: usize, key: &str) -> Option<(usize, usize)> {
+    // Keys of a block all share the indentation of its first line
+    let mut block_indent = None;
+
+    for (i, line) in lines.iter().enumerate().skip(cursor) {
+        let (indent, content) = content_of(line);
+        if content.is_empty() || content.starts_with('#') {
+            continue;
+        }
+        if indent < min_indent {
+            if i > cursor {
+                return None;
+            }
+            continue;
+        }
+
+        let block = *block_indent.get_or_insert(indent);
+        if indent == block
+            && (content.starts_with(&format!("{}:", key))
+                || content.starts_with(&format!("\"{}\":", key)))
+        {
+            return Some((i, indent + 1));
+        }
+    }
+    None
+}
+
+/// Finds the `index`-th sequence item below the key at `cursor`.
+fn find_item(
+    lines: &[&str],
+    cursor: usize,
+    min_indent: usize,
+    index: usize,
+) -> Option<(usize, usize)> {
+    let parent_indent = min_indent.saturating_sub(1);
+    let mut seen = 0;
+
+    for (i, line) in lines.iter().enumerate().skip(cursor + 1) {
+        let indent = line.len() - line.trim_start().len();
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        let is_item = trimmed.starts_with("- ") || trimmed == "-";
+        if indent < parent_indent || (!is_item && indent <= parent_indent) {
+            return None;
+        }
+        if is_item && indent <= parent_indent + 2 {
+            if seen == index {
+                // Keys of the item sit at the dash's indentation or deeper
+                return Some((i, indent));
+            }
+            seen += 1;
+        }
+    }
+    None
+}
+
+/// Indentation and content of a line, treating a leading `- ` sequence
+/// marker as indentation so item keys line up with their siblings.
+fn content_of(line: &str) -> (usize, &str) {
+    let mut indent = line.len() - line.trim_start().len();
+    let mut content = line.trim_start();
+    while let Some(rest) = content.strip_prefix("- ") {
+        indent += 2;
+        content = rest.trim_start();
+    }
+    (indent, content)
+}
+
+/// Returns the schema describing the value at `path`, following
+/// `properties`, object-valued `additionalProperties` and `items`.
+pub fn subschema<'a>(schema: &'a Schema, path: &[Segment]) -> Option<&'a Schema> {
+    let mut current = schema;
+    for segment in path {
+        current = match segment {
+            Segment::Key(key) => current["properties"]
+                .get(key)
+                .or_else(|| Some(&current["additionalProperties"]).filter(|s| s.is_object()))?,
+            Segment::Index(_) => current.get("items")?,
+        };
+    }
+    Some(current)
+}
+
+/// Splits a dotted path into segments. Malformed indexes are skipped, so
+/// callers needing strictness compare the [`render_path`] round trip.
+pub fn parse_path(path: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    for part in path.split('.') {
+        let (key, mut rest) = match part.find('[') {
+            Some(i) => (&part[..i], &part[i..]),
+            None => (part, ""),
+        };
+        if !key.is_empty() && key != "(root)" {
+            segments.push(Segment::Key(key.to_string()));
+        }
+        while let Some(end) = rest.find(']') {
+            if let Ok(index) = rest[1..end].parse() {
+                segments.push(Segment::Index(index));
+            }
+            rest = &rest[end + 1..];
+        }
+    }
+    segments
+}
@@ -0,0 +1,266 @@
+use crate::ci::say;
+use crate::helmfile::Helmfile;
+use crate::tools;
+use crate::utils::check_command_available;
+use colored::*;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+/// Key material a sops file can be decrypted with, read from its `sops:`
+/// metadata block.
+#[derive(Default)]
+pub struct SopsKeys {
+    pub age: Vec<String>,
+    pub pgp: Vec<String>,
+    /// Cloud KMS and Vault keys, which can only be verified by decrypting
+    pub remote: Vec<String>,
+}
+
+/// Outcome of checking a single encrypted file.
+pub struct SecretCheck {
+    pub path: PathBuf,
+    pub release: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Returns the sops key metadata when `path` is a sops-encrypted YAML or
+/// JSON document, `None` for plain files.
+pub fn sops_keys(path: &Path) -> Option<SopsKeys> {
+    let content = fs::read_to_string(path).ok()?;
+    let value: serde_yaml::Value = serde_yaml::from_str(&content).ok()?;
+    let sops = value.get("sops")?.as_mapping()?;
+
+    let mut keys = SopsKeys::default();
+    let mut groups: Vec<&serde_yaml::Mapping> = vec![sops];
+    if let Some(key_groups) = sops
+        .get("key_groups")
+        .and_then(serde_yaml::Value::as_sequence)
+    {
+        groups.extend(key_groups.iter().filter_map(serde_yaml::Value::as_mapping));
+    }
+
+    for group in groups {
+        for (field, kind) in [
+            ("age", "recipient"),
+            ("pgp", "fp"),
+            ("kms", "arn"),
+            ("gcp_kms", "resource_id"),
+            ("azure_kv", "vault_url"),
+            ("hc_vault", "vault_address"),
+        ] {
+            let entries = group
+                .get(field)
+                .and_then(serde_yaml::Value::as_sequence)
+                .into_iter()
+                .flatten()
+                .filter_map(|e| e.get(kind).and_then(serde_yaml::Value::as_str))
+                .map(str::to_string);
+            match field {
+                "age" => keys.age.extend(entries),
+                "pgp" => keys.pgp.extend(entries),
+                _ => keys
+                    .remote
+                    .extend(entries.map(|k| format!("{}: {}", field, k))),
+            }
+        }
+    }
+
+    Some(keys)
+}
+
+/// Checks that every sops-encrypted values or secrets file referenced by
+/// the helmfile can be decrypted with locally available keys. Decrypted
+/// output is discarded and never captured.
+pub fn verify_helmfile(
+    file: &str,
+    environment: Option<&str>,
+    release: Option<&str>,
+    verbose: bool,
+) -> Result<Vec<SecretCheck>, String> {
+    let helmfile = Helmfile::load(file)?;
+    let base = Path::new(file).parent().unwrap_or(Path::new("."));
+
+    let mut checks: Vec<SecretCheck> = Vec::new();
+    for reference in helmfile.file_references(environment, release) {
+        let path = base.join(&reference.path);
+        if checks.iter().any(|c| c.path == path) {
+            continue;
+        }
+
+        if !path.exists() {
+            if reference.secret {
+                checks.push(SecretCheck {
+                    path,
+                    release: reference.release,
+                    error: Some("file not found".to_string()),
+                });
+            }
+            continue;
+        }
+
+        let Some(keys) = sops_keys(&path) else {
+            if reference.secret {
+                checks.push(SecretCheck {
+                    path,
+                    release: reference.release,
+                    error: Some("listed under secrets but not sops-encrypted".to_string()),
+                });
+            }
+            continue;
+        };
+
+        if verbose {
+            say!("🔐 Checking {}", path.display());
+        }
+
+        let error = verify_keys(&keys)
+            .err()
+            .or_else(|| try_decrypt(&path).err());
+        checks.push(SecretCheck {
+            path,
+            release: reference.release,
+            error,
+        });
+    }
+
+    Ok(checks)
+}
+
+/// Runs [`verify_helmfile`] and fails if any encrypted file cannot be
+/// decrypted, listing each offending file. Used before helmfile runs so
+/// missing keys surface before any release is touched.
+pub fn ensure_decryptable(
+    file: &str,
+    environment: Option<&str>,
+    verbose: bool,
+) -> Result<(), String> {
+    let checks = match verify_helmfile(file, environment, None, verbose) {
+        Ok(checks) => checks,
+        Err(e) => {
+            // Heavily templated helmfiles cannot be inspected statically
+            if verbose {
+                say!("⚠️  Skipping secrets check: {}", e);
+            }
+            return Ok(());
+        }
+    };
+
+    if checks.is_empty() {
+        return Ok(());
+    }
+
+    say!("🔐 Verifying {} encrypted values file(s)...", checks.len());
+
+    let failures: Vec<String> = checks
+        .iter()
+        .filter_map(|c| {
+            c.error
+                .as_ref()
+                .map(|e| format!("  • {}: {}", c.path.display(), e))
+        })
+        .collect();
+
+    if !failures.is_empty() {
+        return Err(format!(
+            "Encrypted values cannot be decrypted:\n{}",
+            failures.join("\n")
+        ));
+    }
+
+    say!("{}", "✅ All encrypted values can be decrypted".green());
+    Ok(())
+}
+
+fn verify_keys(keys: &SopsKeys) -> Result<(), String> {
+    // Remote keys cannot be checked offline; the trial decryption decides
+    if !keys.remote.is_empty() || (keys.age.is_empty() && keys.pgp.is_empty()) {
+        return Ok(());
+    }
+
+    if !keys.age.is_empty() {
+        // Identities without a "public key" comment cannot be matched
+        if let Some(recipients) = local_age_recipients() {
+            if recipients.is_empty() || keys.age.iter().any(|k| recipients.contains(k)) {
+                return Ok(());
+            }
+        }
+    }
+
+    if keys.pgp.iter().any(|fp| has_pgp_secret_key(fp)) {
+        return Ok(());
+    }
+
+    let mut missing = Vec::new();
+    if !keys.age.is_empty() {
+        missing.push(format!("age recipient(s) {}", keys.age.join(", ")));
+    }
+    if !keys.pgp.is_empty() {
+        missing.push(format!("PGP fingerprint(s) {}", keys.pgp.join(", ")));
+    }
+    Err(format!("no local key for {}", missing.join(" or ")))
+}
+
+/// Public keys of the local age identities, from the `# public key:`
+/// comments age-keygen writes. `None` when no identity source exists.
+fn local_age_recipients() -> Option<Vec<String>> {
+    let content = match env::var("SOPS_AGE_KEY") {
+        Ok(key) => key,
+        Err(_) => fs::read_to_string(age_key_file()?).ok()?,
+    };
+
+    Some(
+        content
+            .lines()
+            .filter_map(|l| l.trim().strip_prefix("# public key:"))
+            .map(|k| k.trim().to_string())
+            .collect(),
+    )
+}
+
+fn age_key_file() -> Option<PathBuf> {
+    if let Ok(file) = env::var("SOPS_AGE_KEY_FILE") {
+        return Some(PathBuf::from(file));
+    }
+    if let Ok(dir) = env::var("XDG_CONFIG_HOME") {
+        return Some(Path::new(&dir).join("sops").join("age").join("keys.txt"));
+    }
+
+    let home = env::var("HOME").ok()?;
+    let config = if cfg!(target_os = "macos") {
+        Path::new(&home).join("Library").join("Application Support")
+    } else {
+        Path::new(&home).join(".config")
+    };
+    Some(config.join("sops").join("age").join("keys.txt"))
+}
+
+fn has_pgp_secret_key(fingerprint: &str) -> bool {
+    tools::command("gpg")
+        .args(["--list-secret-keys", "--with-colons", fingerprint])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+fn try_decrypt(path: &Path) -> Result<(), String> {
+    check_command_available("sops")?;
+
+    let output = tools::command("sops")
+        .arg("--decrypt")
+        .arg(path)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| format!("failed to execute sops: {}", e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = stderr.lines().next().unwrap_or("sops failed").trim();
+        return Err(reason.to_string());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,201 @@
+use crate::version::Version;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Mutex, OnceLock};
+
+static OVERRIDES: OnceLock<BTreeMap<String, String>> = OnceLock::new();
+
+/// Outcome of the minimum version check, per tool, for this process.
+static VERSION_CHECKS: OnceLock<Mutex<HashMap<String, Result<(), String>>>> = OnceLock::new();
+
+/// Registers tool locations from `tools` in helmctl.yaml, e.g.
+/// `helm: /opt/helm3/helm`. Only the first call has an effect.
+pub fn init(overrides: Option<&BTreeMap<String, String>>) {
+    let _ = OVERRIDES.set(overrides.cloned().unwrap_or_default());
+}
+
+/// The configured location of `name`, if any.
+pub fn override_for(name: &str) -> Option<&'static str> {
+    OVERRIDES.get()?.get(name).map(String::as_str)
+}
+
+/// Locates the executable for `name`, honouring configured overrides,
+/// without spawning a process.
+pub fn resolve(name: &str) -> Option<PathBuf> {
+    find_executable(override_for(name).unwrap_or(name))
+}
+
+/// A `Command` for `name`, using the resolved path when one is found so
+/// overrides apply to every invocation.
+pub fn command(name: &str) -> Command {
+    match resolve(name) {
+        Some(path) => Command::new(path),
+        None => Command::new(name),
+    }
+}
+
+/// Looks up a program the way a shell would: paths containing a
+/// separator are checked directly, bare names are searched in `PATH`.
+pub fn find_executable(program: &str) -> Option<PathBuf> {
+    let candidate = Path::new(program);
+    if candidate.components().count() > 1 {
+        return is_executable(candidate).then(|| candidate.to_path_buf());
+    }
+
+    let path = env::var_os("PATH")?;
+    env::split_paths(&path)
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .flat_map(|dir| executable_names(program).map(move |name| dir.join(name)))
+        .find(|candidate| is_executable(candidate))
+}
+
+#[cfg(unix)]
+fn executable_names(program: &str) -> impl Iterator<Item = String> {
+    std::iter::once(program.to_string())
+}
+
+#[cfg(not(unix))]
+fn executable_names(program: &str) -> impl Iterator<Item = String> {
+    let extensions = env::var("PATHEXT").unwrap_or_else(|_| ".EXE;.CMD;.BAT".to_string());
+    std::iter::once(program.to_string()).chain(
+        extensions
+            .split(';')
+            .map(|ext| format!("{}{}", program, ext.to_lowercase()))
+            .collect::<Vec<_>>(),
+    )
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// Fails when a known tool is older than its supported minimum. Tools
+/// whose version cannot be determined are let through. Checked once per
+/// process.
+pub fn check_minimum_version(name: &str) -> Result<(), String> {
+    let Some(tool) = TOOLS.iter().find(|t| t.name == name) else {
+        return Ok(());
+    };
+
+    let checks = VERSION_CHECKS.get_or_init(Default::default);
+    let mut checks = checks.lock().unwrap_or_else(|e| e.into_inner());
+    checks
+        .entry(name.to_string())
+        .or_insert_with(|| {
+            let Ok(Some(version)) = tool.detect_version() else {
+                return Ok(());
+            };
+            let minimum = tool.minimum_version();
+            if version < minimum {
+                return Err(format!(
+                    "{} {} is older than the minimum supported version {}. Upgrade it or point tools.{} in helmctl.yaml at a newer binary.",
+                    name, version, minimum, name
+                ));
+            }
+            Ok(())
+        })
+        .clone()
+}
+
+/// An external tool helmctl drives, with the versions it is known to
+/// work with.
+pub struct Tool {
+    pub name: &'static str,
+    /// Arguments that make the tool print its own version
+    pub version_args: &'static [&'static str],
+    pub minimum: &'static str,
+    /// First version not yet tested; newer versions only produce a warning
+    pub tested_below: Option<&'static str>,
+    /// Whether core commands (deploy, lint, status) need the tool
+    pub required: bool,
+    pub purpose: &'static str,
+}
+
+pub const TOOLS: [Tool; 5] = [
+    Tool {
+        name: "helm",
+        version_args: &["version", "--short"],
+        minimum: "3.8.0",
+        tested_below: Some("4.0.0"),
+        required: true,
+        purpose: "chart operations",
+    },
+    Tool {
+        name: "helmfile",
+        version_args: &["--version"],
+        minimum: "0.150.0",
+        tested_below: Some("2.0.0"),
+        required: true,
+        purpose: "lint, deploy and status",
+    },
+    Tool {
+        name: "kubectl",
+        version_args: &["version", "--client"],
+        minimum: "1.24.0",
+        tested_below: None,
+        required: true,
+        purpose: "contexts and k8s-deploy",
+    },
+    Tool {
+        name: "kustomize",
+        version_args: &["version"],
+        minimum: "4.0.0",
+        tested_below: None,
+        required: false,
+        purpose: "standalone kustomize builds",
+    },
+    Tool {
+        name: "sops",
+        version_args: &["--version"],
+        minimum: "3.7.0",
+        tested_below: None,
+        required: false,
+        purpose: "encrypted values",
+    },
+];
+
+impl Tool {
+    pub fn minimum_version(&self) -> Version {
+        Version::parse(self.minimum).expect("valid minimum version")
+    }
+
+    /// Runs the tool's version command. `Err` means the tool could not be
+    /// executed at all; an unrecognised output yields `Ok(None)`.
+    pub fn detect_version(&self) -> Result<Option<Version>, String> {
+        let output = command(self.name)
+            .args(self.version_args)
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|e| format!("{} not found: {}", self.name, e))?;
+
+        // Some tools print their version on stderr
+        let text = format!(
+            "{}\n{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        Ok(extract_version(&text))
+    }
+}
+
+/// Finds the first version-looking token in a tool's output, such as
+/// `v3.14.2+g1234` in `helm version --short` or `kustomize/v5.3.0` in
+/// older kustomize builds.
+pub fn extract_version(text: &str) -> Option<Version> {
+    text.split(|c: char| c.is_whitespace() || matches!(c, ':' | ',' | '"' | '/' | '{' | '}'))
+        .filter(|token| {
+            let digits = token.trim_start_matches('v');
+            digits.starts_with(|c: char| c.is_ascii_digit()) && digits.contains('.')
+        })
+        .find_map(Version::parse)
+}
@@ -1,9 +1,13 @@
+use crate::ci::{self, say};
+use crate::redact::{redact, redact_command};
+use crate::tools;
 use chrono::{DateTime, Utc};
 use colored::*;
 use serde::Serialize;
+use std::env;
 use std::fs;
 use std::io::{self, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 
 #[derive(Serialize)]
@@ -22,6 +26,11 @@ pub fn init_logging(log_file: &str) {
     }
 }
 
+/// Prints a command line in verbose mode with sensitive values masked.
+pub fn print_command(cmd: &Command) {
+    say!("🔧 Command: {}", redact_command(cmd));
+}
+
 pub fn log_operation(command: &str, details: &str, success: bool) {
     let entry = LogEntry {
         timestamp: Utc::now(),
@@ -31,7 +40,7 @@ pub fn log_operation(command: &str, details: &str, success: bool) {
             "ERROR".to_string()
         },
         command: command.to_string(),
-        message: details.to_string(),
+        message: redact(details),
         success,
     };
 
@@ -41,26 +50,48 @@ pub fn log_operation(command: &str, details: &str, success: bool) {
     }
 }
 
+/// Exit code returned when a diff finds pending changes, mirroring
+/// `helm diff --detailed-exitcode`.
+pub const EXIT_CODE_CHANGES: i32 = 2;
+
+/// Checks that `command` can be executed and, for the tools helmctl
+/// knows, that it meets the minimum supported version.
 pub fn check_command_available(command: &str) -> Result<(), String> {
-    Command::new("which")
-        .arg(command)
-        .output()
-        .map_err(|_| {
-            format!(
+    if tools::resolve(command).is_none() {
+        return Err(match tools::override_for(command) {
+            Some(path) => format!(
+                "Command '{}' not found at configured path {} (tools.{} in helmctl.yaml).",
+                command, path, command
+            ),
+            None => format!(
                 "Command '{}' not found. Please install {} and make sure it's in your PATH.",
                 command, command
-            )
-        })
-        .and_then(|output| {
-            if output.status.success() {
-                Ok(())
-            } else {
-                Err(format!(
-                    "Command '{}' not found. Please install {} and make sure it's in your PATH.",
-                    command, command
-                ))
-            }
-        })
+            ),
+        });
+    }
+
+    tools::check_minimum_version(command)
+}
+
+/// Where helmctl keeps short-lived private files: `$XDG_RUNTIME_DIR` when
+/// set (per-user and cleared at logout), else the temp directory.
+pub fn private_temp_base() -> PathBuf {
+    env::var_os("XDG_RUNTIME_DIR")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_dir())
+        .unwrap_or_else(env::temp_dir)
+}
+
+/// A fresh directory with an unpredictable name that only the current
+/// user can access. Callers remove it when done.
+pub fn scratch_dir(label: &str) -> Result<PathBuf, String> {
+    let base = private_temp_base();
+    tempfile::Builder::new()
+        .prefix(&format!("helmctl-{}-", label))
+        .tempdir_in(&base)
+        .map(|dir| dir.keep())
+        .map_err(|e| format!("Failed to create a directory in {}: {}", base.display(), e))
 }
 
 pub fn update_helm_repos(verbose: bool) -> Result<(), String> {
@@ -71,9 +102,9 @@ pub fn update_helm_repos(verbose: bool) -> Result<(), String> {
 
     for (name, url) in repos {
         if verbose {
-            println!("📋 Adding/updating Helm repository: {}", name);
+            say!("📋 Adding/updating Helm repository: {}", name);
         }
-        let output = Command::new("helm")
+        let output = tools::command("helm")
             .args(["repo", "add", name, url])
             .output()
             .map_err(|e| format!("Failed to add helm repository {}: {}", name, e))?;
@@ -81,15 +112,15 @@ pub fn update_helm_repos(verbose: bool) -> Result<(), String> {
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
             if !stderr.contains("already exists") && verbose {
-                println!("⚠️  Warning: Could not add repository {}: {}", name, stderr);
+                say!("⚠️  Warning: Could not add repository {}: {}", name, stderr);
             }
         }
     }
 
     if verbose {
-        println!("🔄 Updating Helm repositories...");
+        say!("🔄 Updating Helm repositories...");
     }
-    let mut cmd = Command::new("helm");
+    let mut cmd = tools::command("helm");
     cmd.args(["repo", "update"]);
 
     if !verbose {
@@ -107,32 +138,14 @@ pub fn update_helm_repos(verbose: bool) -> Result<(), String> {
     Ok(())
 }
 
-pub fn check_helm_diff_plugin() -> Result<(), String> {
-    let output = Command::new("helm")
-        .args(["plugin", "list"])
-        .output()
-        .map_err(|e| format!("Failed to check helm plugins: {}", e))?;
-
-    if !output.status.success() {
-        return Err("Failed to check helm plugins".to_string());
-    }
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    if !stdout.contains("diff") {
-        return Err("Helm diff plugin not installed. Install it with: helm plugin install https://github.com/databus23/helm-diff".to_string());
-    }
-
-    Ok(())
-}
-
 pub fn set_kubectl_context(context: &str, verbose: bool) -> Result<(), String> {
-    println!("🔄 Setting kubectl context to: {}", context.yellow());
+    say!("🔄 Setting kubectl context to: {}", context.yellow());
 
-    let mut cmd = Command::new("kubectl");
+    let mut cmd = tools::command("kubectl");
     cmd.args(["config", "use-context", context]);
 
     if verbose {
-        println!("🔧 Command: {:?}", cmd);
+        print_command(&cmd);
     }
 
     let status = cmd
@@ -153,15 +166,65 @@ pub fn confirm_production_deployment(
     let is_prod = environment.is_some_and(|e| e.contains("prod"))
         || context.is_some_and(|c| c.contains("prod"));
 
-    if is_prod {
-        print!("⚠️  You are about to deploy to production. Continue? (y/N): ");
-        io::stdout().flush().unwrap();
-        let mut input = String::new();
-        io::stdin().read_line(&mut input).unwrap();
-        if !input.trim().to_lowercase().starts_with('y') {
-            return Err("Deployment cancelled by user".to_string());
-        }
+    if is_prod && !confirm("⚠️  You are about to deploy to production. Continue?")? {
+        return Err("Deployment cancelled by user".to_string());
     }
 
     Ok(())
 }
+
+/// Asks a yes/no question. `--yes` answers it up front; in CI mode, where
+/// nobody can answer, it fails instead of waiting for input.
+pub fn confirm(question: &str) -> Result<bool, String> {
+    if ci::assume_yes() {
+        say!("{} (y/N): y", question);
+        return Ok(true);
+    }
+    if ci::enabled() {
+        return Err(format!(
+            "Confirmation required in CI mode: {} Pass --yes to proceed.",
+            question.trim_start_matches("⚠️  ")
+        ));
+    }
+
+    print!("{} (y/N): ", question);
+    io::stdout().flush().unwrap();
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).unwrap();
+    Ok(input.trim().to_lowercase().starts_with('y'))
+}
+
+/// Directory where helm caches repository `index.yaml` files, following
+/// helm's own resolution of `HELM_REPOSITORY_CACHE` and `XDG_CACHE_HOME`.
+pub fn helm_repository_cache() -> Result<PathBuf, String> {
+    if let Ok(dir) = env::var("HELM_REPOSITORY_CACHE") {
+        return Ok(PathBuf::from(dir));
+    }
+    if let Ok(dir) = env::var("XDG_CACHE_HOME") {
+        return Ok(Path::new(&dir).join("helm").join("repository"));
+    }
+
+    let home = env::var("HOME")
+        .map_err(|_| "Cannot locate helm repository cache: HOME is not set".to_string())?;
+    let base = if cfg!(target_os = "macos") {
+        Path::new(&home).join("Library").join("Caches")
+    } else {
+        Path::new(&home).join(".cache")
+    };
+    Ok(base.join("helm").join("repository"))
+}
+
+/// Prints one line of unified diff output with the usual coloring.
+pub fn print_diff_line(line: &str) {
+    if line.starts_with("+++") || line.starts_with("---") {
+        println!("{}", line.dimmed());
+    } else if line.starts_with("@@") {
+        println!("{}", line.cyan());
+    } else if line.starts_with('+') {
+        println!("{}", line.green());
+    } else if line.starts_with('-') {
+        println!("{}", line.red());
+    } else {
+        println!("{}", line);
+    }
+}
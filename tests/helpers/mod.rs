@@ -1,6 +1,10 @@
-use tempfile::TempDir;
+// Each test binary uses a different subset of these helpers
+#![allow(dead_code)]
+
+use assert_cmd::Command;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
 
 pub struct TestEnvironment {
     pub temp_dir: TempDir,
@@ -36,6 +40,40 @@ impl TestEnvironment {
         fs::write(&self.config_path, content).unwrap();
     }
 
+    pub fn path(&self) -> &Path {
+        self.temp_dir.path()
+    }
+
+    /// Writes an executable `/bin/sh` script named `name` that stands in
+    /// for the real tool on the PATH of [`TestEnvironment::command`].
+    #[cfg(unix)]
+    pub fn fake_tool(&self, name: &str, script: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = self.path().join(name);
+        fs::write(&path, format!("#!/bin/sh\n{}", script)).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    /// helmctl run from the environment's directory, which is also its only
+    /// PATH entry and its config and state home. KUBECONFIG points at a
+    /// missing file and CI detection is off; set them on the command to
+    /// change that.
+    pub fn command(&self) -> Command {
+        let dir = self.path();
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("helmctl"));
+        cmd.env("PATH", dir)
+            .env("XDG_CONFIG_HOME", dir)
+            .env("XDG_STATE_HOME", dir)
+            .env("KUBECONFIG", dir.join("no-kubeconfig"))
+            .env_remove("CI")
+            .env_remove("GITHUB_ACTIONS")
+            .env_remove("GITHUB_STEP_SUMMARY")
+            .current_dir(dir);
+        cmd
+    }
+
     pub fn helmfile_path_str(&self) -> &str {
         self.helmfile_path.to_str().unwrap()
     }
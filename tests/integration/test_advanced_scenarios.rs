@@ -89,7 +89,7 @@ fn test_config_invalid_concurrency_value() {
 
     cmd.assert()
         .failure()
-        .stderr(predicate::str::contains("Invalid concurrency value"));
+        .stderr(predicate::str::contains("Invalid value for default_concurrency"));
 }
 
 #[test]
@@ -194,7 +194,7 @@ fn test_config_invalid_boolean_value() {
 
     cmd.assert()
         .failure()
-        .stderr(predicate::str::contains("Invalid boolean value"));
+        .stderr(predicate::str::contains("Invalid value for auto_update_repos"));
 }
 
 #[test]
@@ -210,7 +210,7 @@ fn test_config_invalid_log_level() {
 
     cmd.assert()
         .failure()
-        .stderr(predicate::str::contains("Invalid log level"));
+        .stderr(predicate::str::contains("Invalid value for log_level"));
 }
 
 #[test]
@@ -36,6 +36,17 @@ fn test_config_save_and_load() {
                 url: "https://example.com/charts".to_string(),
             }
         ]),
+        default_namespace: None,
+        default_skip_deps: None,
+        default_strict: Some(true),
+        default_wait: None,
+        default_detailed: None,
+        redact_patterns: Some(vec!["*dsn".to_string()]),
+        profiles: None,
+        tools: None,
+        plugins: None,
+        context_aliases: None,
+        ci_summary_file: None,
     };
 
     // Save config